@@ -2,7 +2,7 @@
 
 extern crate test;
 
-use eyeliner::inline;
+use eyeliner::{inline, inline_compiled, AbstractOptions, AbstractSettings, CompiledStylesheet};
 use servo_css_parser::embedder_traits;
 use test::Bencher;
 
@@ -14,6 +14,16 @@ fn bench_dashboard(b: &mut Bencher) {
     b.iter(|| inline(html, Some(css.to_owned()), None, None))
 }
 
+#[bench]
+fn bench_dashboard_compiled(b: &mut Bencher) {
+    embedder_traits::resources::set_for_tests();
+    let html = include_str!("./bootstrap/site/content/docs/4.3/examples/dashboard/index.html");
+    let css = include_str!("./bootstrap/dist/css/bootstrap.css");
+    let compiled_stylesheet =
+        CompiledStylesheet::new(css, None::<AbstractOptions>, None::<AbstractSettings>);
+    b.iter(|| inline_compiled(html, &compiled_stylesheet))
+}
+
 #[bench]
 fn bench_navbar(b: &mut Bencher) {
     embedder_traits::resources::set_for_tests();
@@ -21,3 +31,13 @@ fn bench_navbar(b: &mut Bencher) {
     let css = include_str!("./bootstrap/dist/css/bootstrap.css");
     b.iter(|| inline(html, Some(css.to_owned()), None, None))
 }
+
+#[bench]
+fn bench_navbar_compiled(b: &mut Bencher) {
+    embedder_traits::resources::set_for_tests();
+    let html = include_str!("./bootstrap/site/content/docs/4.3/examples/navbars/index.html");
+    let css = include_str!("./bootstrap/dist/css/bootstrap.css");
+    let compiled_stylesheet =
+        CompiledStylesheet::new(css, None::<AbstractOptions>, None::<AbstractSettings>);
+    b.iter(|| inline_compiled(html, &compiled_stylesheet))
+}
@@ -0,0 +1,540 @@
+use eyeliner::{
+    inline_document, try_inline_document, AbstractOptions, AbstractSettings, CompiledStylesheet,
+    Config, CssSource, Diagnostic, Error, Eyeliner, FileSystemLoader, Location, Severity,
+    StylesheetLoader,
+};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    process,
+    sync::Arc,
+};
+use structopt::StructOpt;
+
+/// A CSS inliner for making emails.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "eyeliner")]
+struct Cli {
+    /// The HTML file to inline. Reads from stdin when omitted or `-`.
+    #[structopt(parse(from_os_str), conflicts_with = "batch")]
+    input: Option<PathBuf>,
+
+    /// A CSS file to inline. May be repeated; files are applied in order.
+    #[structopt(long, parse(from_os_str), number_of_values = 1)]
+    css: Vec<PathBuf>,
+
+    /// The file to write the inlined HTML to. Writes to stdout when omitted.
+    #[structopt(short, long, parse(from_os_str), conflicts_with = "batch")]
+    output: Option<PathBuf>,
+
+    /// A glob of HTML files to inline, e.g. `templates/**/*.html`.
+    #[structopt(long, requires = "output-dir")]
+    batch: Option<String>,
+
+    /// The directory to write batch results to, mirroring the tree matched by `--batch`.
+    #[structopt(long, parse(from_os_str), requires = "batch")]
+    output_dir: Option<PathBuf>,
+
+    /// A TOML or JSON config file of options and settings, which flags override. Defaults to
+    /// `eyeliner.toml` or `eyeliner.json` in the current directory, if either exists.
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Fail on CSS errors instead of reporting them as warnings.
+    #[structopt(long)]
+    strict: bool,
+
+    #[structopt(flatten)]
+    options: OptionFlags,
+
+    #[structopt(flatten)]
+    settings: SettingFlags,
+}
+
+/// Flags for each field of `AbstractOptions`.
+#[derive(Debug, StructOpt)]
+struct OptionFlags {
+    /// Resolve relative URLs against the `<base>` tag or `--base-url`, then remove `<base>` tags.
+    #[structopt(long, value_name = "bool")]
+    absolutize_urls: Option<bool>,
+
+    /// Apply `--style-to-attribute` to `--table-elements`.
+    #[structopt(long, value_name = "bool")]
+    apply_table_element_attributes: Option<bool>,
+
+    /// Create `height` attributes from CSS heights.
+    #[structopt(long, value_name = "bool")]
+    apply_height_attributes: Option<bool>,
+
+    /// Inline CSS from `<link rel="stylesheet">` tags using `--stylesheet-root`.
+    #[structopt(long, value_name = "bool")]
+    apply_link_tags: Option<bool>,
+
+    /// Turn `::before` and `::after` rules into `<span>` elements.
+    #[structopt(long, value_name = "bool")]
+    apply_pseudo_elements: Option<bool>,
+
+    /// Inline CSS from `<style>` tags.
+    #[structopt(long, value_name = "bool")]
+    apply_style_tags: Option<bool>,
+
+    /// Create `width` attributes from CSS widths.
+    #[structopt(long, value_name = "bool")]
+    apply_width_attributes: Option<bool>,
+
+    /// With `--strict`, fail when the inlined HTML is larger than `--size-budget`.
+    #[structopt(long, value_name = "bool")]
+    enforce_size_budget: Option<bool>,
+
+    /// Write longhand CSS properties in `style` attributes instead of collapsing them into
+    /// shorthands.
+    #[structopt(long, value_name = "bool")]
+    expand_shorthands: Option<bool>,
+
+    /// Let existing `style` attributes win over stylesheet rules.
+    #[structopt(long, value_name = "bool")]
+    inline_style_precedence: Option<bool>,
+
+    /// Selectors of elements to insert preserved CSS into.
+    #[structopt(
+        long,
+        value_name = "selector",
+        use_delimiter = true,
+        require_delimiter = true
+    )]
+    insert_preserved_css: Option<Vec<String>>,
+
+    /// Minify the CSS of `style` attributes and preserved CSS rules.
+    #[structopt(long, value_name = "bool")]
+    minify: Option<bool>,
+
+    /// Insert preserved CSS rules before the other children of their element.
+    #[structopt(long, value_name = "bool")]
+    prepend_preserved_css: Option<bool>,
+
+    /// Preserve `@font-face` rules.
+    #[structopt(long, value_name = "bool")]
+    preserve_font_faces: Option<bool>,
+
+    /// Preserve `@font-feature-values` rules.
+    #[structopt(long, value_name = "bool")]
+    preserve_font_feature_values: Option<bool>,
+
+    /// Keep `!important` in inlined styles.
+    #[structopt(long, value_name = "bool")]
+    preserve_important: Option<bool>,
+
+    /// Preserve `@keyframes` rules.
+    #[structopt(long, value_name = "bool")]
+    preserve_keyframes: Option<bool>,
+
+    /// Preserve `@media` rules.
+    #[structopt(long, value_name = "bool")]
+    preserve_media_queries: Option<bool>,
+
+    /// Preserve `@page` rules.
+    #[structopt(long, value_name = "bool")]
+    preserve_pages: Option<bool>,
+
+    /// Preserve rules with stateful pseudo-classes, such as `a:hover`.
+    #[structopt(long, value_name = "bool")]
+    preserve_pseudo_classes: Option<bool>,
+
+    /// Preserve `@supports` rules.
+    #[structopt(long, value_name = "bool")]
+    preserve_supports: Option<bool>,
+
+    /// Remove style rules that match nothing in the HTML document from preserved `@media` rules.
+    #[structopt(long, value_name = "bool")]
+    prune_media_queries: Option<bool>,
+
+    /// Remove `<link rel="stylesheet">` tags after inlining them.
+    #[structopt(long, value_name = "bool")]
+    remove_link_tags: Option<bool>,
+
+    /// Remove `<style>` tags after inlining them.
+    #[structopt(long, value_name = "bool")]
+    remove_style_tags: Option<bool>,
+
+    /// Sort the declarations in each `style` attribute by property name.
+    #[structopt(long, value_name = "bool")]
+    sort_declarations: Option<bool>,
+}
+
+impl From<OptionFlags> for AbstractOptions {
+    fn from(flags: OptionFlags) -> Self {
+        Self {
+            absolutize_urls: flags.absolutize_urls,
+            apply_table_element_attributes: flags.apply_table_element_attributes,
+            apply_height_attributes: flags.apply_height_attributes,
+            apply_link_tags: flags.apply_link_tags,
+            apply_pseudo_elements: flags.apply_pseudo_elements,
+            apply_style_tags: flags.apply_style_tags,
+            apply_width_attributes: flags.apply_width_attributes,
+            enforce_size_budget: flags.enforce_size_budget,
+            expand_shorthands: flags.expand_shorthands,
+            inline_style_precedence: flags.inline_style_precedence,
+            insert_preserved_css: flags.insert_preserved_css,
+            minify: flags.minify,
+            prepend_preserved_css: flags.prepend_preserved_css,
+            preserve_font_faces: flags.preserve_font_faces,
+            preserve_font_feature_values: flags.preserve_font_feature_values,
+            preserve_important: flags.preserve_important,
+            preserve_keyframes: flags.preserve_keyframes,
+            preserve_media_queries: flags.preserve_media_queries,
+            preserve_pages: flags.preserve_pages,
+            preserve_pseudo_classes: flags.preserve_pseudo_classes,
+            preserve_supports: flags.preserve_supports,
+            prune_media_queries: flags.prune_media_queries,
+            remove_link_tags: flags.remove_link_tags,
+            remove_style_tags: flags.remove_style_tags,
+            sort_declarations: flags.sort_declarations,
+        }
+    }
+}
+
+/// Flags for each field of `AbstractSettings`.
+#[derive(Debug, StructOpt)]
+struct SettingFlags {
+    /// Elements that get `width` attributes.
+    #[structopt(
+        long,
+        value_name = "element",
+        use_delimiter = true,
+        require_delimiter = true
+    )]
+    width_elements: Option<Vec<String>>,
+
+    /// Elements that get `height` attributes.
+    #[structopt(
+        long,
+        value_name = "element",
+        use_delimiter = true,
+        require_delimiter = true
+    )]
+    height_elements: Option<Vec<String>>,
+
+    /// CSS properties to copy to table element attributes.
+    #[structopt(
+        long,
+        value_name = "property=attribute",
+        use_delimiter = true,
+        require_delimiter = true,
+        parse(try_from_str = parse_property_attribute)
+    )]
+    style_to_attribute: Option<Vec<(String, String)>>,
+
+    /// Elements that get attributes from `--style-to-attribute`.
+    #[structopt(
+        long,
+        value_name = "element",
+        use_delimiter = true,
+        require_delimiter = true
+    )]
+    table_elements: Option<Vec<String>>,
+
+    /// Elements that never get inlined styles.
+    #[structopt(
+        long,
+        value_name = "element",
+        use_delimiter = true,
+        require_delimiter = true
+    )]
+    non_visual_elements: Option<Vec<String>>,
+
+    /// CSS properties that are never inlined.
+    #[structopt(
+        long,
+        value_name = "property",
+        use_delimiter = true,
+        require_delimiter = true
+    )]
+    excluded_properties: Option<Vec<String>>,
+
+    /// Pseudo-classes whose rules are preserved instead of inlined.
+    #[structopt(
+        long,
+        value_name = "pseudo-class",
+        use_delimiter = true,
+        require_delimiter = true
+    )]
+    stateful_pseudo_classes: Option<Vec<String>>,
+
+    /// The pixel size of `rem` and `em` units.
+    #[structopt(long, value_name = "px")]
+    root_font_size: Option<f32>,
+
+    /// Size, in bytes, that the inlined HTML should stay under.
+    #[structopt(long, value_name = "bytes")]
+    size_budget: Option<usize>,
+
+    /// Inline the HTML as a fragment, parsed as the contents of this element, such as `body`.
+    #[structopt(long, value_name = "element")]
+    fragment_context_element: Option<String>,
+
+    /// The URL that relative URLs are resolved against.
+    #[structopt(long, value_name = "url")]
+    base_url: Option<String>,
+
+    /// The directory that `<link>` and `@import` stylesheets are loaded from.
+    #[structopt(long, value_name = "dir", parse(from_os_str))]
+    stylesheet_root: Option<PathBuf>,
+}
+
+impl From<SettingFlags> for AbstractSettings {
+    fn from(flags: SettingFlags) -> Self {
+        Self {
+            width_elements: flags.width_elements,
+            height_elements: flags.height_elements,
+            style_to_attribute: flags
+                .style_to_attribute
+                .map(|pairs| pairs.into_iter().collect()),
+            table_elements: flags.table_elements,
+            non_visual_elements: flags.non_visual_elements,
+            excluded_properties: flags.excluded_properties,
+            stateful_pseudo_classes: flags.stateful_pseudo_classes,
+            root_font_size: flags.root_font_size,
+            size_budget: flags.size_budget,
+            fragment_context_element: flags.fragment_context_element,
+            base_url: flags.base_url,
+            stylesheet_loader: flags
+                .stylesheet_root
+                .map(|root| Arc::new(FileSystemLoader::new(root)) as Arc<dyn StylesheetLoader>),
+        }
+    }
+}
+
+fn parse_property_attribute(pair: &str) -> Result<(String, String), String> {
+    let mut split = pair.splitn(2, '=');
+    match (split.next(), split.next()) {
+        (Some(property), Some(attribute)) if !property.is_empty() && !attribute.is_empty() => {
+            Ok((property.to_string(), attribute.to_string()))
+        }
+        _ => Err(format!("expected `property=attribute`, got `{}`", pair)),
+    }
+}
+
+/// Each `--css` file, and the line of the joined CSS that it starts at.
+type CssFiles = [(PathBuf, u32)];
+
+/// Describes where CSS is, relative to the `--css` file it is in, or the HTML file `source` for
+/// CSS from a `<style />` tag.
+fn css_location(
+    source: &str,
+    css_files: &CssFiles,
+    css_source: &CssSource,
+    location: Location,
+) -> String {
+    let css_file = css_files
+        .iter()
+        .rev()
+        .find(|(_, line)| *line <= location.line);
+    match *css_source {
+        CssSource::Css => match css_file {
+            Some((path, line)) => format!(
+                "{}:{}:{}",
+                path.display(),
+                location.line - line + 1,
+                location.column
+            ),
+            None => format!("css:{}:{}", location.line, location.column),
+        },
+        CssSource::StyleTag(_) => format!(
+            "{} {}:{}:{}",
+            source, css_source, location.line, location.column
+        ),
+        CssSource::Href(_) => format!("{}:{}:{}", css_source, location.line, location.column),
+    }
+}
+
+fn describe_error(source: &str, css_files: &CssFiles, error: &Error) -> String {
+    match *error {
+        Error::Css {
+            ref message,
+            source: ref css_source,
+            line,
+            column,
+        } => format!(
+            "{}: CSS error: {}",
+            css_location(source, css_files, css_source, Location { line, column }),
+            message
+        ),
+        _ => format!("{}: {}", source, error),
+    }
+}
+
+fn print_diagnostic(source: &str, css_files: &CssFiles, diagnostic: &Diagnostic) {
+    let severity = match diagnostic.severity {
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    match (&diagnostic.source, diagnostic.location) {
+        (Some(css_source), Some(location)) => eprintln!(
+            "{}: {}: {}",
+            severity,
+            css_location(source, css_files, css_source, location),
+            diagnostic.message
+        ),
+        (None, Some(location)) => eprintln!(
+            "{}: {}:{}:{}: {}",
+            severity, source, location.line, location.column, diagnostic.message
+        ),
+        _ => eprintln!("{}: {}: {}", severity, source, diagnostic.message),
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|error| format!("{}: {}", parent.display(), error))?;
+    }
+    fs::write(path, contents).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn inline_file(
+    html: &str,
+    source: &str,
+    css_files: &CssFiles,
+    compiled_stylesheet: &CompiledStylesheet,
+    strict: bool,
+) -> Result<String, String> {
+    let mut eyeliner = if strict {
+        Eyeliner::try_from_compiled(html, compiled_stylesheet)
+            .map_err(|error| describe_error(source, css_files, &error))?
+    } else {
+        Eyeliner::from_compiled(html, compiled_stylesheet)
+    };
+
+    let inlined_html = if strict {
+        try_inline_document(&mut eyeliner).map_err(|error| format!("{}: {}", source, error))?
+    } else {
+        inline_document(&mut eyeliner)
+    };
+
+    // Diagnostics of the compiled stylesheet come first, and are already printed once.
+    for diagnostic in &eyeliner.diagnostics[compiled_stylesheet.diagnostics.len()..] {
+        print_diagnostic(source, css_files, diagnostic);
+    }
+
+    Ok(inlined_html)
+}
+
+/// The leading components of a glob pattern that contain no wildcards. Batch outputs are written
+/// relative to this directory.
+fn glob_root(pattern: &str) -> PathBuf {
+    let path = Path::new(pattern);
+    let mut root: PathBuf = path
+        .components()
+        .take_while(|component| match component {
+            Component::Normal(name) => !name.to_string_lossy().contains(&['*', '?', '['][..]),
+            _ => true,
+        })
+        .collect();
+    if root == path {
+        root.pop();
+    }
+    root
+}
+
+fn run_batch(
+    pattern: &str,
+    output_dir: &Path,
+    css_files: &CssFiles,
+    compiled_stylesheet: &CompiledStylesheet,
+    strict: bool,
+) -> Result<(), String> {
+    let root = glob_root(pattern);
+    let paths = glob::glob(pattern).map_err(|error| format!("{}: {}", pattern, error))?;
+
+    for path in paths {
+        let path = path.map_err(|error| error.to_string())?;
+        if !path.is_file() {
+            continue;
+        }
+
+        let html = read_file(&path)?;
+        let inlined_html = inline_file(
+            &html,
+            &path.display().to_string(),
+            css_files,
+            compiled_stylesheet,
+            strict,
+        )?;
+
+        let relative_path = path.strip_prefix(&root).unwrap_or(&path);
+        write_file(&output_dir.join(relative_path), &inlined_html)?;
+    }
+
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    // Each file is followed by a line break, so the next one starts on a new line.
+    let mut css = String::new();
+    let mut css_files = vec![];
+    for path in &cli.css {
+        css_files.push((path.clone(), css.matches('\n').count() as u32 + 1));
+        css.push_str(&read_file(path)?);
+        css.push('\n');
+    }
+
+    let config = match &cli.config {
+        Some(path) => Config::load(path),
+        None => Config::discover(".").map(Option::unwrap_or_default),
+    }
+    .map_err(|error| error.to_string())?
+    .merge(Config {
+        options: AbstractOptions::from(cli.options),
+        settings: AbstractSettings::from(cli.settings),
+    });
+    let compiled_stylesheet = if cli.strict {
+        CompiledStylesheet::try_new(&css, Some(config.options), Some(config.settings))
+            .map_err(|error| describe_error("css", &css_files, &error))?
+    } else {
+        CompiledStylesheet::new(&css, Some(config.options), Some(config.settings))
+    };
+    for diagnostic in &compiled_stylesheet.diagnostics {
+        print_diagnostic("css", &css_files, diagnostic);
+    }
+
+    if let (Some(pattern), Some(output_dir)) = (&cli.batch, &cli.output_dir) {
+        return run_batch(
+            pattern,
+            output_dir,
+            &css_files,
+            &compiled_stylesheet,
+            cli.strict,
+        );
+    }
+
+    let (html, source) = match &cli.input {
+        Some(path) if path != Path::new("-") => (read_file(path)?, path.display().to_string()),
+        _ => {
+            let mut html = String::new();
+            io::stdin()
+                .read_to_string(&mut html)
+                .map_err(|error| format!("stdin: {}", error))?;
+            (html, String::from("stdin"))
+        }
+    };
+    let inlined_html = inline_file(&html, &source, &css_files, &compiled_stylesheet, cli.strict)?;
+
+    match &cli.output {
+        Some(path) => write_file(path, &inlined_html),
+        None => io::stdout()
+            .write_all(inlined_html.as_bytes())
+            .map_err(|error| format!("stdout: {}", error)),
+    }
+}
+
+fn main() {
+    if let Err(message) = run(Cli::from_args()) {
+        eprintln!("eyeliner: {}", message);
+        process::exit(1);
+    }
+}
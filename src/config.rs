@@ -0,0 +1,83 @@
+use super::{error::Error, options::AbstractOptions, settings::AbstractSettings};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// File names looked for by `Config::discover`, in order.
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["eyeliner.toml", "eyeliner.json"];
+
+/// Options and settings shared through a config file, such as `eyeliner.toml`:
+///
+/// ```toml
+/// [options]
+/// remove_style_tags = false
+///
+/// [settings]
+/// width_elements = ["table", "td", "img"]
+/// ```
+///
+/// Unknown keys are rejected, so typos don't silently fall back to the defaults.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub options: AbstractOptions,
+
+    #[serde(default)]
+    pub settings: AbstractSettings,
+}
+
+impl Config {
+    /// Parses a config from a string of TOML.
+    pub fn from_toml(toml: &str) -> Result<Self, Error> {
+        toml::from_str(toml).map_err(|error| Error::Config {
+            path: String::from("<toml>"),
+            message: error.to_string(),
+        })
+    }
+
+    /// Parses a config from a string of JSON.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|error| Error::Config {
+            path: String::from("<json>"),
+            message: error.to_string(),
+        })
+    }
+
+    /// Reads a config file. Files ending in `.json` are parsed as JSON, all others as TOML.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let config_error = |message: String| Error::Config {
+            path: path.display().to_string(),
+            message,
+        };
+
+        let contents = fs::read_to_string(path).map_err(|error| config_error(error.to_string()))?;
+        match path.extension() {
+            Some(extension) if extension == "json" => {
+                serde_json::from_str(&contents).map_err(|error| config_error(error.to_string()))
+            }
+            _ => toml::from_str(&contents).map_err(|error| config_error(error.to_string())),
+        }
+    }
+
+    /// Reads the first of `CONFIG_FILE_NAMES` found in `directory`, if any.
+    pub fn discover<P: AsRef<Path>>(directory: P) -> Result<Option<Self>, Error> {
+        for file_name in CONFIG_FILE_NAMES.iter() {
+            let path = directory.as_ref().join(file_name);
+            if path.is_file() {
+                return Self::load(path).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns this config with every option and setting that is set in `overrides` replaced by
+    /// the override.
+    pub fn merge(self, overrides: Self) -> Self {
+        Self {
+            options: self.options.merge(overrides.options),
+            settings: self.settings.merge(overrides.settings),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use super::error::Error;
+use servo_css_parser::cssparser::SourceLocation;
+use std::fmt;
+
+/// How serious a diagnostic is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// Information about what the inliner did, such as how much CSS it removed.
+    Info,
+
+    /// Valid CSS that the inliner ignored.
+    Warning,
+
+    /// CSS that could not be parsed.
+    Error,
+}
+
+/// Where CSS came from. The CSS stylesheet is joined from each of these, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CssSource {
+    /// The `css` argument, or the CSS that a compiled stylesheet was created from.
+    Css,
+
+    /// A `<style />` tag in the HTML document, by its index among every `<style />` tag in the
+    /// HTML document, starting at 0.
+    StyleTag(usize),
+
+    /// A stylesheet loaded from a `<link rel="stylesheet" />` tag or an `@import` rule, by its
+    /// `href`.
+    Href(String),
+}
+
+impl fmt::Display for CssSource {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CssSource::Css => write!(f, "css"),
+            CssSource::StyleTag(index) => write!(f, "<style>[{}]", index),
+            CssSource::Href(ref href) => write!(f, "{}", href),
+        }
+    }
+}
+
+/// A position in CSS. Positions are ordered by line, then by column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    /// The line number, starting at 1.
+    pub line: u32,
+
+    /// The column number, starting at 1.
+    pub column: u32,
+}
+
+impl From<SourceLocation> for Location {
+    fn from(source_location: SourceLocation) -> Self {
+        // Servo counts lines from 0, but editors count them from 1.
+        Self {
+            line: source_location.line + 1,
+            column: source_location.column,
+        }
+    }
+}
+
+/// A problem found while inlining, such as CSS that was ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+
+    /// A description of the problem.
+    pub message: String,
+
+    /// Where the problem is in `source`, if it is known.
+    pub location: Option<Location>,
+
+    /// The CSS that the problem is in, if it is known.
+    pub source: Option<CssSource>,
+}
+
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        // The location of a CSS error is kept separately from its message, so that it is only
+        // reported once.
+        match error {
+            Error::Css {
+                message,
+                source,
+                line,
+                column,
+            } => Self {
+                severity: Severity::Error,
+                message,
+                location: Some(Location { line, column }),
+                source: Some(source),
+            },
+            _ => Self {
+                severity: Severity::Error,
+                message: error.to_string(),
+                location: None,
+                source: None,
+            },
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use super::diagnostic::CssSource;
+use std::{error, fmt};
+
+/// Errors that can occur while inlining HTML and CSS.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The URL used to resolve the CSS stylesheet, `Settings.base_url`, could not be parsed.
+    InvalidUrl(String),
+
+    /// The CSS could not be parsed, at the given line and column of the CSS it came from.
+    Css {
+        message: String,
+        source: CssSource,
+        line: u32,
+        column: u32,
+    },
+
+    /// A selector could not be parsed.
+    InvalidSelector(String),
+
+    /// A CSS property name is not known.
+    UnknownProperty(String),
+
+    /// An external stylesheet could not be loaded.
+    Load { href: String, message: String },
+
+    /// A stylesheet imports itself, through the chain of `href`s given.
+    ImportCycle(Vec<String>),
+
+    /// A config file could not be read or parsed.
+    Config { path: String, message: String },
+
+    /// The inlined HTML document is larger than `Settings.size_budget`, in bytes.
+    SizeBudgetExceeded { size: usize, budget: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidUrl(ref message) => write!(f, "Invalid stylesheet URL: {}", message),
+            Error::Css {
+                ref message,
+                ref source,
+                line,
+                column,
+            } => write!(
+                f,
+                "CSS error at {}:{}:{}: {}",
+                source, line, column, message
+            ),
+            Error::InvalidSelector(ref selector) => write!(f, "Invalid selector: {}", selector),
+            Error::UnknownProperty(ref property) => write!(f, "Unknown CSS property: {}", property),
+            Error::Load {
+                ref href,
+                ref message,
+            } => write!(f, "Could not load stylesheet `{}`: {}", href, message),
+            Error::ImportCycle(ref chain) => {
+                write!(f, "Circular `@import`: {}", chain.join(" -> "))
+            }
+            Error::Config {
+                ref path,
+                ref message,
+            } => write!(f, "Invalid config file `{}`: {}", path, message),
+            Error::SizeBudgetExceeded { size, budget } => write!(
+                f,
+                "Inlined HTML is {} bytes, over the size budget of {} bytes",
+                size, budget
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {}
@@ -1,35 +1,88 @@
 use super::{
-    hash::HashableNodeRef, options::ConcreteOptions, rules::Rules, settings::ConcreteSettings,
+    diagnostic::{CssSource, Diagnostic, Severity},
+    error::Error,
+    hash::HashableNodeRef,
+    imports::{is_absolute_href, resolve_imports},
+    minify::{minify_declarations, minify_stylesheet},
+    options::ConcreteOptions,
+    pseudo_elements::{parse_content, split_pseudo_element, ContentItem, PseudoElement},
+    rules::{PreservedRuleKind, Rules},
+    selector::expand_selector,
+    settings::ConcreteSettings,
+    size_report::SizeReport,
+    source_map::JoinedCss,
+    stylesheet::{
+        collect_stylesheet_rules, into_options, into_settings, lenient_stylesheet_url,
+        parse_stylesheet, strip_pseudo_classes, stylesheet_url, validate, CompiledStylesheet,
+    },
     traits::*,
+    urls::{absolutize_css_urls, absolutize_url},
 };
-use html5ever::{local_name, namespace_url, ns, QualName};
-use kuchiki::{parse_html, traits::*, NodeRef};
+use html5ever::{local_name, namespace_url, ns, LocalName, QualName};
+use kuchiki::{parse_fragment, parse_html, traits::*, Attribute, ExpandedName, NodeRef};
 use servo_css_parser::{
-    parse,
-    style::{
-        properties::{
-            declaration_block::{parse_style_attribute, Importance, PropertyDeclarationBlock},
-            PropertyDeclarationId, PropertyId,
-        },
-        stylesheets::{CssRule, StyleRule},
+    cssparser::{Color, Parser, ParserInput},
+    style::properties::{
+        declaration_block::{parse_style_attribute, Importance, PropertyDeclarationBlock},
+        LonghandId, PropertyDeclaration, PropertyDeclarationId, PropertyId,
     },
-    types::{MediaList, Origin, QuirksMode, ServoStylesheet as Stylesheet, Url},
+    types::{QuirksMode, ServoStylesheet as Stylesheet, Url},
 };
 use std::{
     collections::{
         hash_map::Entry::{Occupied, Vacant},
         HashMap,
     },
+    fmt,
     string::ToString,
 };
 
-trait ExtendFromPropertyDeclarationBlock {
-    fn extend_from_block(self: &mut Self, block: &PropertyDeclarationBlock) -> &mut Self;
+trait CascadePropertyDeclarationBlocks {
+    fn cascade_blocks(self: &mut Self, blocks: &[&PropertyDeclarationBlock]) -> &mut Self;
+}
+impl CascadePropertyDeclarationBlocks for PropertyDeclarationBlock {
+    /// Pushes the declarations of `blocks`, which must already be in cascade order, so that the
+    /// winning declaration for each property is the one a browser would use: all normal
+    /// declarations are pushed before any `!important` ones.
+    fn cascade_blocks(self: &mut Self, blocks: &[&PropertyDeclarationBlock]) -> &mut Self {
+        for important in &[false, true] {
+            for block in blocks {
+                for (declaration, importance) in block.declaration_importance_iter() {
+                    if importance.important() == *important {
+                        self.push(declaration.clone(), importance);
+                    }
+                }
+            }
+        }
+
+        self
+    }
+}
+
+trait ExtendFromLowerPrecedencePropertyDeclarationBlock {
+    fn extend_from_lower_precedence_block(
+        self: &mut Self,
+        block: &PropertyDeclarationBlock,
+    ) -> &mut Self;
 }
-impl ExtendFromPropertyDeclarationBlock for PropertyDeclarationBlock {
-    fn extend_from_block(self: &mut Self, block: &PropertyDeclarationBlock) -> &mut Self {
-        for (declartion, importance) in block.declaration_importance_iter() {
-            self.push(declartion.clone(), importance);
+impl ExtendFromLowerPrecedencePropertyDeclarationBlock for PropertyDeclarationBlock {
+    /// Pushes the declarations of `block`, keeping any existing declaration for the same property
+    /// unless it is overridden by an `!important` declaration.
+    fn extend_from_lower_precedence_block(
+        self: &mut Self,
+        block: &PropertyDeclarationBlock,
+    ) -> &mut Self {
+        for (declaration, importance) in block.declaration_importance_iter() {
+            let overrides = match self.get(declaration.id()) {
+                Some((_, existing_importance)) => {
+                    importance.important() && !existing_importance.important()
+                }
+                None => true,
+            };
+
+            if overrides {
+                self.push(declaration.clone(), importance);
+            }
         }
 
         self
@@ -50,23 +103,135 @@ impl RemoveImportanceFromPropertyDeclarationBlock for PropertyDeclarationBlock {
     }
 }
 
-trait RemoveExcludedPropertiesFromPropertyDeclarationBlock {
-    fn remove_excluded_properties(self: &mut Self, properties: &[String]) -> &mut Self;
+trait SortPropertyDeclarationBlock {
+    fn sort_declarations(self: &mut Self) -> &mut Self;
 }
-impl RemoveExcludedPropertiesFromPropertyDeclarationBlock for PropertyDeclarationBlock {
-    fn remove_excluded_properties(self: &mut Self, properties: &[String]) -> &mut Self {
-        for property_id in properties {
-            if let Ok(ref id) = PropertyId::parse_enabled_for_all_content(property_id) {
-                if let Some(first_declaration_to_remove) = self.first_declaration_to_remove(id) {
-                    self.remove_property(id, first_declaration_to_remove);
-                }
-            }
+impl SortPropertyDeclarationBlock for PropertyDeclarationBlock {
+    /// Sorts the declarations by property name. The sort is stable, so any declarations of the
+    /// same property stay in cascade order.
+    fn sort_declarations(self: &mut Self) -> &mut Self {
+        let mut declarations = self.declaration_importance_iter().collect::<Vec<_>>();
+        declarations.sort_by_cached_key(|(declaration, _)| property_name(declaration));
+
+        let mut sorted_block = PropertyDeclarationBlock::new();
+        for (declaration, importance) in declarations {
+            sorted_block.push(declaration.clone(), importance);
         }
+        *self = sorted_block;
 
         self
     }
 }
 
+/// Returns the name of the CSS property of a declaration, including the `--` of custom
+/// properties.
+fn property_name(declaration: &PropertyDeclaration) -> String {
+    match declaration.id() {
+        PropertyDeclarationId::Custom(name) => format!("--{}", name),
+        id => id.name().to_string(),
+    }
+}
+
+trait LonghandsToCss {
+    fn longhands_to_css(self: &Self, dest: &mut String) -> fmt::Result;
+}
+impl LonghandsToCss for PropertyDeclarationBlock {
+    /// Serializes every declaration as a longhand CSS property, without collapsing any of them
+    /// into a shorthand.
+    fn longhands_to_css(self: &Self, dest: &mut String) -> fmt::Result {
+        let mut declarations = vec![];
+        for (declaration, importance) in self.declaration_importance_iter() {
+            let mut value = String::default();
+            declaration.to_css(&mut value)?;
+
+            let importance = if importance.important() {
+                " !important"
+            } else {
+                ""
+            };
+            declarations.push(format!(
+                "{}: {}{};",
+                property_name(declaration),
+                value,
+                importance
+            ));
+        }
+
+        dest.push_str(&declarations.join(" "));
+        Ok(())
+    }
+}
+
+/// Serializes declarations into the value of a `style` attribute. `!important` is removed unless
+/// `Options.preserve_important` is set, and declarations are only sorted, shorthands are only
+/// expanded, and the CSS is only minified when `Options.sort_declarations`,
+/// `Options.expand_shorthands`, and `Options.minify` are set.
+fn to_style_attribute(
+    block: &PropertyDeclarationBlock,
+    options: &ConcreteOptions,
+) -> Option<String> {
+    let mut block = block.clone();
+    if !options.preserve_important {
+        block.remove_importance();
+    }
+    if options.sort_declarations {
+        block.sort_declarations();
+    }
+
+    let mut css = String::default();
+    let result = if options.expand_shorthands {
+        block.longhands_to_css(&mut css)
+    } else {
+        block.to_css(&mut css)
+    };
+    if result.is_err() {
+        return None;
+    }
+
+    if options.minify {
+        css = minify_declarations(&css);
+    }
+
+    Some(css)
+}
+
+/// Attribute that excludes an element, and any element inside it, from being processed.
+const IGNORE_ATTRIBUTE: &str = "data-eyeliner-ignore";
+
+/// Attribute of a `<style />` tag that preserved CSS rules are inserted into.
+const PRESERVE_ATTRIBUTE: &str = "data-eyeliner-preserve";
+
+/// Attribute of a `<style />` tag that is left in the HTML document instead of being inlined.
+const EMBED_ATTRIBUTE: &str = "data-embed";
+
+/// Attribute of a `<style />` tag that is inlined, but not removed from the HTML document.
+const KEEP_ATTRIBUTE: &str = "data-eyeliner-keep";
+
+/// Attributes that control how `<style />` tags are processed, removed from them once inlining is
+/// done. Other elements can have attributes with the same names for other uses, so they are left.
+const STYLE_CONTROL_ATTRIBUTES: [&str; 3] = [PRESERVE_ATTRIBUTE, EMBED_ATTRIBUTE, KEEP_ATTRIBUTE];
+
+/// Attributes that hold a URL, which are resolved by `Options.absolutize_urls`.
+const URL_ATTRIBUTES: [&str; 3] = ["src", "href", "background"];
+
+/// Text of a comment that is replaced by a `<style />` tag of preserved CSS rules.
+const PRESERVE_COMMENT: &str = "eyeliner:preserved";
+
+/// Whether an element, or any element it is inside, has the `data-eyeliner-ignore` attribute.
+fn is_ignored(node: &NodeRef) -> bool {
+    node.inclusive_ancestors()
+        .any(|ancestor| match ancestor.as_element() {
+            Some(element) => element.attributes.borrow().contains(IGNORE_ATTRIBUTE),
+            None => false,
+        })
+}
+
+/// Elements that can't have children, so they never get `::before` and `::after` elements.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
 /// Data and methods related to modifying HTML with CSS.
 #[derive(Clone, Debug)]
 pub struct Eyeliner {
@@ -82,11 +247,18 @@ pub struct Eyeliner {
     /// Settings referenced by features enabled through options.
     pub settings: ConcreteSettings,
 
-    /// A hashmap of HTML elements to CSS style.
-    pub node_style_map: HashMap<HashableNodeRef, PropertyDeclarationBlock>,
+    /// HTML elements and their CSS style, in document order, so that anything done to each of
+    /// them happens in the same order on every run.
+    pub styled_elements: Vec<(NodeRef, PropertyDeclarationBlock)>,
 
     /// Data collected from the CSS stylesheet.
     pub rules: Rules,
+
+    /// Problems found while inlining, such as CSS that could not be parsed or was ignored.
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// The size of the HTML document, measured once inlining is done.
+    pub size_report: Option<SizeReport>,
 }
 
 impl Eyeliner {
@@ -95,7 +267,16 @@ impl Eyeliner {
     /// 1.  Opitionally extracts the CSS in `<style />` tags from the HTML document. Then,
     ///     optionally removes the `<style />` tag from the HTML document.
     ///
-    /// 2.  Any CSS extraced gets appended to the `css` argument.
+    /// 2.  Optionally loads the CSS of `<link rel="stylesheet" />` tags in the HTML document with
+    ///     `Settings.stylesheet_loader`. Then, optionally removes the `<link />` tag from the HTML
+    ///     document.
+    ///
+    /// 3.  Any CSS extraced gets appended to the `css` argument, in document order.
+    ///
+    /// 4.  `@import` rules in any of the CSS are replaced with the CSS they import, loaded with
+    ///     `Settings.stylesheet_loader`.
+    ///
+    /// Any CSS that can't be parsed or loaded is ignored and recorded in `diagnostics`.
     ///
     pub fn new<T: Into<ConcreteOptions>, U: Into<ConcreteSettings>>(
         html: &str,
@@ -103,102 +284,393 @@ impl Eyeliner {
         options: Option<T>,
         settings: Option<U>,
     ) -> Self {
-        let options = match options {
-            Some(o) => o.into(),
-            None => ConcreteOptions::default(),
-        };
-        let settings = match settings {
-            Some(s) => s.into(),
-            None => ConcreteSettings::default(),
+        let settings = into_settings(settings);
+        let (url, url_diagnostic) = lenient_stylesheet_url(&settings);
+
+        let mut eyeliner = Self::parse(html, css, into_options(options), settings, url).0;
+        eyeliner.diagnostics.splice(0..0, url_diagnostic);
+
+        eyeliner
+    }
+
+    /// Create a new instance to inline HTML with CSS, the same way as `new`, but returns an error
+    /// if any CSS can't be parsed or loaded, any selector in `Options.insert_preserved_css` is
+    /// invalid, or any CSS property in `Settings` is unknown.
+    pub fn try_new<T: Into<ConcreteOptions>, U: Into<ConcreteSettings>>(
+        html: &str,
+        css: Option<String>,
+        options: Option<T>,
+        settings: Option<U>,
+    ) -> Result<Self, Error> {
+        let options = into_options(options);
+        let settings = into_settings(settings);
+        validate(&options, &settings)?;
+
+        let url = stylesheet_url(&settings)?;
+        let (eyeliner, mut errors) = Self::parse(html, css, options, settings, url);
+        if !errors.is_empty() {
+            return Err(errors.remove(0));
+        }
+
+        Ok(eyeliner)
+    }
+
+    /// Create a new instance to inline HTML with a compiled stylesheet, using its options and
+    /// settings. Rules from the compiled stylesheet come before any CSS extracted from the HTML
+    /// document, which is handled the same way as `new`.
+    pub fn from_compiled(html: &str, compiled_stylesheet: &CompiledStylesheet) -> Self {
+        // The compiled stylesheet already has a warning for an invalid `Settings.base_url`.
+        let (url, _) = lenient_stylesheet_url(&compiled_stylesheet.settings);
+
+        Self::parse_compiled(html, compiled_stylesheet, url).0
+    }
+
+    /// Create a new instance to inline HTML with a compiled stylesheet, the same way as
+    /// `from_compiled`, but returns an error if any CSS extracted from the HTML document can't be
+    /// parsed or loaded.
+    pub fn try_from_compiled(
+        html: &str,
+        compiled_stylesheet: &CompiledStylesheet,
+    ) -> Result<Self, Error> {
+        let url = stylesheet_url(&compiled_stylesheet.settings)?;
+        let (eyeliner, mut errors) = Self::parse_compiled(html, compiled_stylesheet, url);
+        if !errors.is_empty() {
+            return Err(errors.remove(0));
+        }
+
+        Ok(eyeliner)
+    }
+
+    /// Returns the preserved CSS rules collected, in the same order as the CSS stylesheet, for
+    /// callers that insert them into the HTML themselves, such as when inlining a fragment.
+    pub fn preserved_css(self: &Self) -> String {
+        let preserved_css = self
+            .rules
+            .preserved
+            .iter()
+            .map(|rule| rule.css.as_str())
+            .collect::<Vec<_>>();
+
+        if self.options.minify {
+            minify_stylesheet(&preserved_css.join(""))
+        } else {
+            preserved_css.join("\n")
+        }
+    }
+
+    /// Parses the HTML document, starting with the rules and diagnostics of a compiled
+    /// stylesheet, returning any errors found in the CSS of the HTML document.
+    fn parse_compiled(
+        html: &str,
+        compiled_stylesheet: &CompiledStylesheet,
+        url: Url,
+    ) -> (Self, Vec<Error>) {
+        let (mut eyeliner, errors) = Self::parse(
+            html,
+            None,
+            compiled_stylesheet.options.clone(),
+            compiled_stylesheet.settings.clone(),
+            url,
+        );
+
+        // Style rules are shared with the compiled stylesheet, and only preserved rules, which
+        // are pruned for each HTML document, are copied.
+        let imports = std::mem::take(&mut eyeliner.rules.preserved);
+        let unparsed_rules = std::mem::take(&mut eyeliner.rules.unparsed);
+        let source_map = std::mem::take(&mut eyeliner.rules.source_map);
+        eyeliner.rules = compiled_stylesheet.rules.clone();
+        eyeliner.rules.unparsed = unparsed_rules;
+        eyeliner.rules.source_map = source_map;
+
+        // `@import` rules kept from the HTML document go after the ones kept from the compiled
+        // stylesheet, since they have to come before any other rule.
+        let position = eyeliner
+            .rules
+            .preserved
+            .iter()
+            .take_while(|rule| rule.kind == PreservedRuleKind::Import)
+            .count();
+        eyeliner.rules.preserved.splice(position..position, imports);
+
+        let mut diagnostics = compiled_stylesheet.diagnostics.clone();
+        diagnostics.append(&mut eyeliner.diagnostics);
+        eyeliner.diagnostics = diagnostics;
+
+        (eyeliner, errors)
+    }
+
+    /// Parses the HTML document and the CSS stylesheet, returning any errors found in the CSS.
+    fn parse(
+        html: &str,
+        css: Option<String>,
+        options: ConcreteOptions,
+        settings: ConcreteSettings,
+        url: Url,
+    ) -> (Self, Vec<Error>) {
+        let mut errors = vec![];
+        let mut rules = Rules::default();
+        let loader = settings.stylesheet_loader.as_deref();
+        let mut joined_css = JoinedCss::default();
+        resolve_imports(
+            &css.unwrap_or_default(),
+            &CssSource::Css,
+            loader,
+            &mut vec![],
+            &mut rules.preserved,
+            &mut errors,
+            &mut joined_css,
+        );
+        let document = match settings.fragment_context_element {
+            Some(ref context_element) => parse_fragment(
+                QualName::new(None, ns!(html), LocalName::from(context_element.as_str())),
+                vec![],
+            )
+            .one(html),
+            None => parse_html().one(html),
         };
 
-        let mut css = css.unwrap_or_else(String::new);
-        let document = parse_html().one(html);
+        if let Ok(nodes) = document.select("style, link[rel~=stylesheet][href]") {
+            // Collected first, since detaching nodes while selecting them stops the iteration.
+            let mut style_tag_index = 0;
+            for node in nodes.collect::<Vec<_>>() {
+                if node.name.local == local_name!("style") {
+                    // Every `<style />` tag is counted, so the index matches the HTML document.
+                    let source = CssSource::StyleTag(style_tag_index);
+                    style_tag_index += 1;
+
+                    // Placeholders for preserved CSS rules and embedded CSS are never inlined.
+                    let (is_embedded, is_kept) = {
+                        let attributes = node.attributes.borrow();
+                        (
+                            [PRESERVE_ATTRIBUTE, EMBED_ATTRIBUTE, IGNORE_ATTRIBUTE]
+                                .iter()
+                                .any(|a| attributes.contains(*a)),
+                            attributes.contains(KEEP_ATTRIBUTE),
+                        )
+                    };
+                    if !options.apply_style_tags || is_embedded {
+                        continue;
+                    }
 
-        if options.apply_style_tags {
-            if let Ok(nodes) = document.select("style") {
-                for node in nodes {
-                    css += &node.text_contents();
-                    if options.remove_style_tags {
+                    resolve_imports(
+                        &node.text_contents(),
+                        &source,
+                        loader,
+                        &mut vec![],
+                        &mut rules.preserved,
+                        &mut errors,
+                        &mut joined_css,
+                    );
+                    if options.remove_style_tags && !is_kept {
                         node.as_node().detach();
                     }
+
+                    continue;
+                }
+
+                let attributes = node.attributes.borrow();
+                if !options.apply_link_tags || attributes.contains(IGNORE_ATTRIBUTE) {
+                    continue;
+                }
+
+                let loader = match loader {
+                    Some(l) => l,
+                    None => continue,
+                };
+
+                // Like `@import` rules, tags for stylesheets on another host that the loader
+                // can't load are left for the email client to load.
+                let href = attributes.get("href").unwrap_or("");
+                let loaded_css = match loader.load(href) {
+                    Ok(c) => c,
+                    Err(_) if is_absolute_href(href) => continue,
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
+                    }
+                };
+                let media = match attributes.get("media") {
+                    Some(media) if !media.trim().is_empty() && media.trim() != "all" => Some(media),
+                    _ => None,
+                };
+
+                if let Some(media) = media {
+                    joined_css.push_generated(&format!("@media {} {{\n", media));
+                }
+                resolve_imports(
+                    &loaded_css,
+                    &CssSource::Href(href.to_string()),
+                    Some(loader),
+                    &mut vec![href.to_string()],
+                    &mut rules.preserved,
+                    &mut errors,
+                    &mut joined_css,
+                );
+                if media.is_some() {
+                    joined_css.push_generated("\n}\n");
+                }
+
+                if options.remove_link_tags {
+                    node.as_node().detach();
                 }
             }
         }
 
-        let url = Url::parse("about::test").unwrap();
-        let origin = Origin::UserAgent;
-        let quirks_mode = QuirksMode::NoQuirks;
-        let media = MediaList::empty();
-        let stylesheet = parse(&css, url, origin, quirks_mode, media);
+        let mut diagnostics = errors.iter().cloned().map(Diagnostic::from).collect();
+        let (stylesheet, unparsed_rules) = parse_stylesheet(
+            &joined_css.css,
+            url,
+            &joined_css.source_map,
+            &mut errors,
+            &mut diagnostics,
+        );
+        rules.unparsed = unparsed_rules;
+        rules.source_map = joined_css.source_map;
 
-        Self {
+        let eyeliner = Self {
             document,
             stylesheet,
             options,
             settings,
-            node_style_map: HashMap::new(),
-            rules: Rules::default(),
-        }
+            styled_elements: vec![],
+            rules,
+            diagnostics,
+            size_report: None,
+        };
+
+        (eyeliner, errors)
     }
 }
 
 impl CollectRules for Eyeliner {
     /// Collects CSS rules from the CSS stylesheet for other methods to use.
     /// Optionally removes any excluded CSS properties.
-    /// Optionally preserves `@media` and `@font-face` rules.
+    /// Optionally preserves `@media`, `@font-face`, `@font-feature-values`, `@keyframes`, `@page`,
+    /// and `@supports` rules, and style rules with stateful pseudo-classes.
     fn collect_rules(self: &mut Self) -> &mut Self {
-        {
-            let read_guard = &self.stylesheet.shared_lock.read();
-            for css_rule in &self
-                .stylesheet
-                .contents
-                .rules
-                .as_ref()
-                .read_with(read_guard)
-                .0
-            {
-                match *css_rule {
-                    CssRule::Style(ref style_rule_locked) => {
-                        let style_rule = style_rule_locked.as_ref().read_with(read_guard);
-                        let StyleRule {
-                            ref selectors,
-                            block: ref block_locked,
-                            ..
-                        } = *style_rule;
-
-                        use servo_css_parser::cssparser::ToCss;
-                        let mut block = block_locked.as_ref().read_with(read_guard).clone();
-                        block.remove_excluded_properties(&self.settings.excluded_properties);
-                        self.rules.style.push((selectors.to_css_string(), block));
-                    }
+        collect_stylesheet_rules(
+            &self.stylesheet,
+            &self.options,
+            &self.settings,
+            &mut self.rules,
+        );
+
+        self
+    }
+}
+
+impl ApplyRules for Eyeliner {
+    /// Inlines the CSS rules extracted from the CSS stylesheet into the HTML document.
+    ///
+    /// 1.  CSS rules are sorted into cascade order: by specificity, then by source order.
+    ///
+    /// 2.  For each CSS rule selector (excluding pseudo-selectors), find the matching nodes in the
+    ///     HTML document. Skips any non-visual elements. Selectors that are skipped or can't be
+    ///     matched are recorded in `diagnostics`.
+    ///
+    /// 3.  Each elements style is collected into `styled_elements`, in document order. If any
+    ///     element has a `style` attribute is is extended by each of the CSS rules that apply to
+    ///     it, normal declarations first and `!important` declarations last. Optionally lets
+    ///     declarations in the `style` attribute take precedence over non-`!important` CSS rules.
+    ///     Optionally preserves `!important`.
+    ///
+    fn apply_rules(self: &mut Self) -> &mut Self {
+        let mut cascade = self.rules.style.iter().collect::<Vec<_>>();
 
-                    CssRule::Media(ref media_rule_locked) => {
-                        if !self.options.preserve_media_queries {
-                            continue;
-                        }
+        // The sort is stable, so rules with the same specificity stay in source order.
+        cascade.sort_by_key(|rule| rule.specificity);
 
-                        let media_rule = media_rule_locked.as_ref().read_with(read_guard);
+        // `HashableNodeRef` hashes the address of the node, which doesn't change with its data.
+        #[allow(clippy::mutable_key_type)]
+        let mut matched_blocks: HashMap<HashableNodeRef, Vec<&PropertyDeclarationBlock>> =
+            HashMap::new();
 
-                        use servo_css_parser::style::shared_lock::ToCssWithGuard;
-                        self.rules.media.push(media_rule.to_css_string(read_guard));
-                    }
+        for rule in cascade {
+            // Pseudo-elements can't be styled inline, so their selectors are never matched against
+            // the document. `::before` and `::after` can be turned into elements instead.
+            let is_before_or_after = split_pseudo_element(&rule.selector).is_some();
+            if is_before_or_after || rule.selector.contains("::") {
+                if !(is_before_or_after && self.options.apply_pseudo_elements) {
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        message: format!("Skipped pseudo-element selector `{}`", rule.selector),
+                        location: Some(rule.location),
+                        source: Some(rule.source.clone()),
+                    });
+                }
+                continue;
+            }
 
-                    CssRule::FontFace(ref font_face_rule_data_locked) => {
-                        if !self.options.preserve_font_faces {
-                            continue;
-                        }
+            let nodes = match self.document.select(&rule.selector) {
+                Ok(n) => n,
+                _ => {
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        message: format!("Unsupported selector `{}`", rule.selector),
+                        location: Some(rule.location),
+                        source: Some(rule.source.clone()),
+                    });
+                    continue;
+                }
+            };
 
-                        let font_face_rule_data =
-                            font_face_rule_data_locked.as_ref().read_with(read_guard);
+            for node in nodes {
+                if self
+                    .settings
+                    .non_visual_elements
+                    .contains(&node.name.local.chars().as_str().to_lowercase())
+                    || is_ignored(node.as_node())
+                {
+                    continue;
+                }
 
-                        use servo_css_parser::style::shared_lock::ToCssWithGuard;
-                        self.rules
-                            .font_face
-                            .push(font_face_rule_data.to_css_string(read_guard));
+                match matched_blocks.entry(HashableNodeRef::new(&node)) {
+                    Occupied(mut entry) => {
+                        entry.get_mut().push(&rule.block);
                     }
+                    Vacant(entry) => {
+                        entry.insert(vec![&rule.block]);
+                    }
+                };
+            }
+        }
+
+        // Elements are styled in document order, rather than the order of the hashmap.
+        let mut styled_elements = vec![];
+        for node in self.document.inclusive_descendants() {
+            let hash = HashableNodeRef { node };
+            let blocks = match matched_blocks.remove(&hash) {
+                Some(b) => b,
+                None => continue,
+            };
+
+            let element = match hash.node.as_element() {
+                Some(e) => e,
+                None => continue,
+            };
+
+            let mut exisiting_style = parse_style_attribute(
+                element.attributes.borrow().get("style").unwrap_or(""),
+                &self.stylesheet.contents.url_data.read(),
+                None,
+                QuirksMode::NoQuirks,
+            );
+            if self.options.inline_style_precedence {
+                let mut cascaded_style = PropertyDeclarationBlock::new();
+                cascaded_style.cascade_blocks(&blocks);
+                exisiting_style.extend_from_lower_precedence_block(&cascaded_style);
+            } else {
+                exisiting_style.cascade_blocks(&blocks);
+            }
+
+            styled_elements.push((hash.node, exisiting_style));
+        }
+        self.styled_elements = styled_elements;
 
-                    _ => (),
+        for (node, block) in &self.styled_elements {
+            if let Some(element) = node.as_element() {
+                if let Some(css) = to_style_attribute(block, &self.options) {
+                    element.attributes.borrow_mut().insert("style", css);
                 }
             }
         }
@@ -207,107 +679,324 @@ impl CollectRules for Eyeliner {
     }
 }
 
-impl ApplyRules for Eyeliner {
-    /// Inlines the CSS rules extracted from the CSS stylesheet into the HTML document.
+impl ApplyPseudoElements for Eyeliner {
+    /// Optionally turns the CSS rules for `::before` and `::after` pseudo-elements into elements.
     ///
-    /// 1.  For each CSS rule selector (excluding pseudo-selectors), find the matching nodes in the
-    ///     HTML document. Skips any non-visual elements.
+    /// 1.  CSS rules are sorted into cascade order, and each selector without its pseudo-element
+    ///     is matched against the HTML document. Skips any void or non-visual elements.
     ///
-    /// 2.  Each elements style is hashmapped. If any element has a `style` attribute is is
-    ///     extended by each of the CSS rules that apply to it. Optionally preserves `!important`.
+    /// 2.  The `content` property is evaluated: strings are used as is and `attr()` is replaced
+    ///     by the attribute of the element. Nothing is generated without `content`, or for `none`
+    ///     and `normal`. Rules with any other `content` are skipped and recorded in `diagnostics`.
     ///
-    fn apply_rules(self: &mut Self) -> &mut Self {
-        for (selector, block) in self.rules.style.clone() {
-            // TODO: using `::` seems to break things.
-            // While testing using Bootstrap CSS, `::after` and `::before` give stack overflows.
-            if selector.contains("::") {
-                continue;
+    /// 3.  A `<span>` with the text of `content` and the rest of the declarations in a `style`
+    ///     attribute is inserted as the first child of the element for `::before`, or the last
+    ///     child for `::after`.
+    ///
+    fn apply_pseudo_elements(self: &mut Self) -> &mut Self {
+        if !self.options.apply_pseudo_elements {
+            return self;
+        }
+
+        let content_id = PropertyDeclarationId::Longhand(LonghandId::Content);
+
+        let mut cascade = self
+            .rules
+            .style
+            .iter()
+            .filter_map(|rule| {
+                split_pseudo_element(&rule.selector)
+                    .map(|(selector, pseudo_element)| (rule, selector, pseudo_element))
+            })
+            .collect::<Vec<_>>();
+        cascade.sort_by_key(|(rule, ..)| rule.specificity);
+
+        // `HashableNodeRef` hashes the address of the node, which doesn't change with its data.
+        #[allow(clippy::mutable_key_type)]
+        let mut matched_blocks: HashMap<
+            (HashableNodeRef, PseudoElement),
+            Vec<&PropertyDeclarationBlock>,
+        > = HashMap::new();
+
+        for (rule, selector, pseudo_element) in cascade {
+            if let Some((declaration, _)) = rule.block.get(content_id) {
+                let mut value = String::default();
+                if declaration.to_css(&mut value).is_err() || parse_content(&value).is_none() {
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        message: format!(
+                            "Unsupported `content: {}` for pseudo-element selector `{}`",
+                            value, rule.selector
+                        ),
+                        location: Some(rule.location),
+                        source: Some(rule.source.clone()),
+                    });
+                    continue;
+                }
             }
 
             let nodes = match self.document.select(&selector) {
                 Ok(n) => n,
-                _ => continue,
+                _ => {
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        message: format!("Unsupported selector `{}`", rule.selector),
+                        location: Some(rule.location),
+                        source: Some(rule.source.clone()),
+                    });
+                    continue;
+                }
             };
 
             for node in nodes {
-                if self
-                    .settings
-                    .non_visual_elements
-                    .contains(&node.name.local.chars().as_str().to_lowercase())
+                let element_name = node.name.local.chars().as_str().to_lowercase();
+                if VOID_ELEMENTS.contains(&element_name.as_str())
+                    || self.settings.non_visual_elements.contains(&element_name)
+                    || is_ignored(node.as_node())
                 {
                     continue;
                 }
 
-                match self.node_style_map.entry(HashableNodeRef::new(&node)) {
+                match matched_blocks.entry((HashableNodeRef::new(&node), pseudo_element)) {
                     Occupied(mut entry) => {
-                        entry.get_mut().extend_from_block(&block);
+                        entry.get_mut().push(&rule.block);
                     }
                     Vacant(entry) => {
-                        let mut exisiting_style = parse_style_attribute(
-                            node.attributes.borrow_mut().get("style").unwrap_or(""),
-                            &self.stylesheet.contents.url_data.read(),
-                            None,
-                            QuirksMode::NoQuirks,
-                        );
-                        exisiting_style.extend_from_block(&block);
-                        entry.insert(exisiting_style.clone());
+                        entry.insert(vec![&rule.block]);
                     }
                 };
             }
         }
 
-        for (hash, block) in &self.node_style_map {
-            let mut cloned_block = block.clone();
-            if !self.options.preserve_important {
-                cloned_block.remove_importance();
+        // Pseudo-elements are generated in document order, rather than the order of the hashmap.
+        let keys = self
+            .document
+            .inclusive_descendants()
+            .flat_map(|node| {
+                vec![
+                    (
+                        HashableNodeRef { node: node.clone() },
+                        PseudoElement::Before,
+                    ),
+                    (HashableNodeRef { node }, PseudoElement::After),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let mut generated_elements = vec![];
+        for key in keys {
+            let blocks = match matched_blocks.remove(&key) {
+                Some(b) => b,
+                None => continue,
+            };
+            let (hash, pseudo_element) = key;
+
+            let element = match hash.node.as_element() {
+                Some(e) => e,
+                None => continue,
+            };
+
+            let mut cascaded_style = PropertyDeclarationBlock::new();
+            cascaded_style.cascade_blocks(&blocks);
+
+            let mut content = String::default();
+            match cascaded_style.get(content_id) {
+                Some((declaration, _)) if declaration.to_css(&mut content).is_ok() => (),
+                _ => continue,
+            };
+
+            let text = match parse_content(&content) {
+                Some(Some(items)) => items
+                    .iter()
+                    .map(|item| match item {
+                        ContentItem::Text(string) => string.to_string(),
+                        ContentItem::Attribute(name) => element
+                            .attributes
+                            .borrow()
+                            .get(name.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                    })
+                    .collect::<String>(),
+                _ => continue,
+            };
+
+            let mut style = PropertyDeclarationBlock::new();
+            for (declaration, importance) in cascaded_style.declaration_importance_iter() {
+                if declaration.id() != content_id {
+                    style.push(declaration.clone(), importance);
+                }
             }
 
-            if let Some(element) = hash.node.as_element() {
-                let mut css = String::default();
-                cloned_block.to_css(&mut css).unwrap();
-                element.attributes.borrow_mut().insert("style", css);
+            let css = match to_style_attribute(&style, &self.options) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            let mut attributes = vec![];
+            if !css.is_empty() {
+                attributes.push((
+                    ExpandedName::new(ns!(), local_name!("style")),
+                    Attribute {
+                        prefix: None,
+                        value: css,
+                    },
+                ));
+            }
+
+            let span_node = NodeRef::new_element(
+                QualName {
+                    prefix: None,
+                    ns: ns!(html),
+                    local: local_name!("span"),
+                },
+                attributes,
+            );
+            if !text.is_empty() {
+                span_node.append(NodeRef::new_text(text));
             }
+
+            match pseudo_element {
+                PseudoElement::Before => hash.node.prepend(span_node.clone()),
+                PseudoElement::After => hash.node.append(span_node.clone()),
+            };
+
+            generated_elements.push((span_node, style));
+        }
+
+        if generated_elements.is_empty() {
+            return self;
         }
 
+        // Generated elements are put in document order along with the elements already styled.
+        #[allow(clippy::mutable_key_type)]
+        let mut styles = self
+            .styled_elements
+            .drain(..)
+            .chain(generated_elements)
+            .map(|(node, style)| (HashableNodeRef { node }, style))
+            .collect::<HashMap<_, _>>();
+        self.styled_elements = self
+            .document
+            .inclusive_descendants()
+            .filter_map(|node| {
+                let style = styles.remove(&HashableNodeRef { node: node.clone() })?;
+                Some((node, style))
+            })
+            .collect();
+
         self
     }
 }
 
+/// Converts a CSS length into the value of an HTML dimension attribute.
+///
+/// Pixels are rounded, `em`, `rem`, and `pt` are converted to whole pixels, and percentages are
+/// only used when `allow_percentage` is set. Any other value can't be represented.
+fn to_dimension_attribute(
+    value: &str,
+    root_font_size: f32,
+    allow_percentage: bool,
+) -> Option<String> {
+    if value.ends_with('%') {
+        if allow_percentage {
+            return Some(value.to_string());
+        }
+
+        return None;
+    }
+
+    for (unit, pixels_per_unit) in &[
+        ("px", 1.0),
+        ("rem", root_font_size),
+        ("em", root_font_size),
+        ("pt", 4.0 / 3.0),
+    ] {
+        if !value.ends_with(unit) {
+            continue;
+        }
+
+        let number = match value[..value.len() - unit.len()].parse::<f32>() {
+            Ok(n) => n,
+            _ => return None,
+        };
+
+        return Some((number * pixels_per_unit).round().to_string());
+    }
+
+    None
+}
+
 impl ApplyAttributes for Eyeliner {
-    /// Iterates over all elements and applies a matching attribute if it has the given CSS
-    /// property.
-    fn apply_attributes(self: &Self, property: &str) -> &Self {
-        for (hash, block) in &self.node_style_map {
-            let property_declaration = block.get(PropertyDeclarationId::Longhand(
-                PropertyId::parse_enabled_for_all_content(property)
-                    .unwrap()
-                    .longhand_id()
-                    .unwrap(),
-            ));
+    /// Iterates over all specified elements and applies a matching attribute if it has the given
+    /// CSS property.
+    ///
+    /// 1.  The value of the CSS property is used, falling back to the value of its `max-`
+    ///     counterpart when the CSS property is missing, `auto`, or has a unit that can't be
+    ///     represented as an attribute.
+    ///
+    /// 2.  Percentages are only applied to table elements, and `em`, `rem`, and `pt` lengths are
+    ///     converted to pixels.
+    ///
+    /// 3.  If the CSS property is `auto` and no fallback applies, any existing attribute is
+    ///     removed so it doesn't conflict with the CSS.
+    ///
+    fn apply_attributes(self: &Self, property: &str, elements: &[String]) -> &Self {
+        let longhand_ids = [property.to_string(), format!("max-{}", property)]
+            .iter()
+            .filter_map(|p| PropertyId::parse_enabled_for_all_content(p).ok())
+            .filter_map(|id| id.longhand_id())
+            .collect::<Vec<_>>();
+
+        for (node, block) in &self.styled_elements {
+            let element = match node.as_element() {
+                Some(e) => e,
+                None => continue,
+            };
 
-            if property_declaration.is_none() {
+            let element_name = element.name.local.chars().as_str().to_lowercase();
+            if !elements.contains(&element_name) {
                 continue;
             }
 
-            let element = hash.node.as_element().unwrap();
+            let allow_percentage = self.settings.table_elements.contains(&element_name);
+            let mut is_auto = false;
+            let mut attribute = None;
 
-            let mut attributes = element.attributes.borrow_mut();
-            let mut value = String::default();
-            property_declaration.unwrap().0.to_css(&mut value).unwrap();
+            for longhand_id in &longhand_ids {
+                let property_declaration =
+                    match block.get(PropertyDeclarationId::Longhand(*longhand_id)) {
+                        Some((d, _)) => d,
+                        None => continue,
+                    };
 
-            if value.ends_with("px") {
-                attributes.insert(property, value.replace("px", ""));
-                continue;
-            }
+                let mut value = String::default();
+                if property_declaration.to_css(&mut value).is_err() {
+                    continue;
+                }
 
-            if value.ends_with('%')
-                && self
-                    .settings
-                    .table_elements
-                    .contains(&element.name.local.chars().as_str().to_lowercase())
-            {
-                attributes.insert(property, value);
+                if value == "auto" {
+                    is_auto = true;
+                    continue;
+                }
+
+                attribute =
+                    to_dimension_attribute(&value, self.settings.root_font_size, allow_percentage);
+                if attribute.is_some() {
+                    break;
+                }
             }
+
+            let mut attributes = element.attributes.borrow_mut();
+            match attribute {
+                Some(a) => {
+                    attributes.insert(property, a);
+                }
+                None if is_auto => {
+                    attributes.remove(property);
+                }
+                None => (),
+            };
         }
 
         self
@@ -315,26 +1004,63 @@ impl ApplyAttributes for Eyeliner {
 }
 
 impl ApplyWidthAttributes for Eyeliner {
-    /// Optionally iterates over all elements and applies a `width` attribute if it has a `width`
-    /// CSS property applied to it.
+    /// Optionally iterates over all elements in `Settings.width_elements` and applies a `width`
+    /// attribute if it has a `width` or `max-width` CSS property applied to it.
     fn apply_width_attributes(self: &Self) -> &Self {
         if !self.options.apply_width_attributes {
             return self;
         }
 
-        self.apply_attributes("width")
+        self.apply_attributes("width", &self.settings.width_elements)
     }
 }
 
 impl ApplyHeightAttributes for Eyeliner {
-    /// Optionally iterates over all elements and applies a `height` attribute if it has a `height`
-    /// CSS property applied to it.
+    /// Optionally iterates over all elements in `Settings.height_elements` and applies a `height`
+    /// attribute if it has a `height` or `max-height` CSS property applied to it.
     fn apply_height_attributes(self: &Self) -> &Self {
         if !self.options.apply_height_attributes {
             return self;
         }
 
-        self.apply_attributes("height")
+        self.apply_attributes("height", &self.settings.height_elements)
+    }
+}
+
+/// Converts the value of a CSS property into the value of an HTML attribute.
+///
+/// A `url()` is unwrapped, since attributes such as `background` take a bare URL. The initial
+/// values that a shorthand such as `background` sets its other longhands to, `none` and a
+/// transparent color, and CSS-wide keywords and any other value with a `url()` can't be
+/// represented.
+fn to_table_element_attribute(value: &str) -> Option<String> {
+    if ["none", "initial", "inherit", "unset"]
+        .iter()
+        .any(|keyword| value.eq_ignore_ascii_case(keyword))
+    {
+        return None;
+    }
+
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    if let Ok(Color::RGBA(rgba)) = Color::parse(&mut parser) {
+        if rgba.alpha == 0 && parser.is_exhausted() {
+            return None;
+        }
+    }
+
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    let url = match parser.expect_url() {
+        Ok(u) => u.as_ref().to_string(),
+        Err(_) if value.contains("url(") => return None,
+        Err(_) => return Some(value.to_string()),
+    };
+
+    if parser.is_exhausted() {
+        Some(url)
+    } else {
+        None
     }
 }
 
@@ -344,15 +1070,21 @@ impl ApplyTableElementAttributes for Eyeliner {
     /// 1.  Iterates over all elements and matches those that are specified table elements.
     ///
     /// 2.  If elements have style properties that are mapped to attributes, then their mapped
-    ///     attributes are applied.
+    ///     attributes are applied. A shorthand is mapped when all of its longhands are set, and a
+    ///     longhand is mapped even when it was set by a shorthand, such as `background-color` by
+    ///     `background`, unless the shorthand left it at its initial value, such as a transparent
+    ///     `background-color`. The URL of `background-image` is mapped without `url()`.
     ///
     fn apply_table_element_attributes(self: &Self) -> &Self {
         if !self.options.apply_table_element_attributes {
             return self;
         }
 
-        for (hash, block) in &self.node_style_map {
-            let element = hash.node.as_element().unwrap();
+        for (node, block) in &self.styled_elements {
+            let element = match node.as_element() {
+                Some(e) => e,
+                None => continue,
+            };
 
             if !self
                 .settings
@@ -364,18 +1096,194 @@ impl ApplyTableElementAttributes for Eyeliner {
 
             let mut attributes = element.attributes.borrow_mut();
 
-            for property_declaration in block.declarations() {
-                let property = property_declaration.id().name().to_string();
-                let attribute = self.settings.style_to_attribute.get::<str>(&property);
+            // Shorthands are always expanded into longhands when CSS is parsed, so the value of
+            // each property is read from the block rather than from its declarations.
+            for (property, attribute) in &self.settings.style_to_attribute {
+                let property_id = match PropertyId::parse_enabled_for_all_content(property) {
+                    Ok(id) => id,
+                    Err(_) => continue,
+                };
 
-                if attribute.is_none() {
+                let mut css = String::default();
+                if block.property_value_to_css(&property_id, &mut css).is_err() || css.is_empty() {
                     continue;
                 }
 
-                let mut css = String::default();
-                property_declaration.to_css(&mut css).unwrap();
+                if let Some(value) = to_table_element_attribute(&css) {
+                    attributes.insert(attribute.clone(), value);
+                }
+            }
+        }
+
+        self
+    }
+}
+
+impl PruneMediaQueries for Eyeliner {
+    /// Drops style rules inside preserved `@media` rules when none of their selectors match an
+    /// element in the HTML document, then drops `@media` rules that have no rules left. Rules that
+    /// aren't style rules, and selectors that kuchiki can't match, are always kept.
+    fn prune_media_queries(self: &mut Self) -> &mut Self {
+        if !self.options.prune_media_queries {
+            return self;
+        }
+
+        let document = &self.document;
+        let stateful_pseudo_classes = &self.settings.stateful_pseudo_classes;
+        let may_match = |selector: &str| {
+            let selector = match split_pseudo_element(selector) {
+                Some((s, _)) => s,
+                None => selector.to_string(),
+            };
+
+            expand_selector(&selector).iter().any(|selector| {
+                match document.select(&strip_pseudo_classes(selector, stateful_pseudo_classes)) {
+                    Ok(mut nodes) => nodes.next().is_some(),
+                    Err(_) => true,
+                }
+            })
+        };
+
+        let mut bytes_before = 0;
+        let mut bytes_after = 0;
+        let mut preserved = vec![];
+
+        for mut rule in self.rules.preserved.drain(..) {
+            let css = match rule.media {
+                Some(ref mut media) => {
+                    media.rules.retain(|nested_rule| {
+                        nested_rule.selectors.is_empty()
+                            || nested_rule.selectors.iter().any(|s| may_match(s))
+                    });
+
+                    if media.rules.is_empty() {
+                        bytes_before += rule.css.len();
+                        continue;
+                    }
+
+                    let nested_css = media
+                        .rules
+                        .iter()
+                        .map(|nested_rule| format!("  {}", nested_rule.css.replace('\n', "\n  ")))
+                        .collect::<Vec<_>>();
+
+                    format!("@media {} {{\n{}\n}}", media.query, nested_css.join("\n"))
+                }
+                None => {
+                    preserved.push(rule);
+                    continue;
+                }
+            };
+
+            bytes_before += rule.css.len();
+            bytes_after += css.len();
+            rule.css = css;
+            preserved.push(rule);
+        }
+
+        self.rules.preserved = preserved;
+
+        if bytes_before > bytes_after {
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Info,
+                message: format!(
+                    "Pruned {} bytes of unused `@media` CSS",
+                    bytes_before - bytes_after
+                ),
+                location: None,
+                source: None,
+            });
+        }
+
+        self
+    }
+}
+
+/// Returns the URL that relative URLs in the HTML document are resolved against: the `href` of
+/// its first `<base>` tag, resolved against `Settings.base_url`, or otherwise `Settings.base_url`.
+fn document_base_url(document: &NodeRef, settings: &ConcreteSettings) -> Option<Url> {
+    let base_url = settings
+        .base_url
+        .as_ref()
+        .and_then(|url| Url::parse(url).ok());
+    let href = document
+        .select_first("base[href]")
+        .ok()
+        .and_then(|base| base.attributes.borrow().get("href").map(String::from));
+
+    match (href, base_url) {
+        (Some(href), Some(base_url)) => base_url.join(&href).ok().or(Some(base_url)),
+        (Some(href), None) => Url::parse(&href).ok(),
+        (None, base_url) => base_url,
+    }
+}
+
+impl AbsolutizeUrls for Eyeliner {
+    /// Optionally resolves relative URLs in the HTML document and in preserved CSS rules.
+    ///
+    /// 1.  The base URL is found from the `<base>` tag and `Settings.base_url`. Nothing is resolved
+    ///     without one.
+    ///
+    /// 2.  `src`, `href`, and `background` attributes, and `url()` values in `style` attributes,
+    ///     `<style />` tags, and preserved CSS rules are resolved against the base URL.
+    ///
+    /// 3.  `<base>` tags are removed, so they can't change how the absolute URLs are resolved.
+    ///
+    fn absolutize_urls(self: &mut Self) -> &mut Self {
+        if !self.options.absolutize_urls {
+            return self;
+        }
+
+        let base_url = match document_base_url(&self.document, &self.settings) {
+            Some(u) => u,
+            None => return self,
+        };
 
-                attributes.insert(attribute.unwrap().clone(), css);
+        // The elements are collected first, since the text of `<style />` tags is replaced.
+        let elements = self.document.descendants().elements().collect::<Vec<_>>();
+        for element in elements {
+            if element.name.local == local_name!("base") {
+                element.as_node().detach();
+                continue;
+            }
+
+            {
+                let mut attributes = element.attributes.borrow_mut();
+                for attribute in &URL_ATTRIBUTES {
+                    let absolute_url = attributes
+                        .get(*attribute)
+                        .and_then(|url| absolutize_url(url, &base_url));
+                    if let Some(url) = absolute_url {
+                        attributes.insert(*attribute, url);
+                    }
+                }
+
+                let style = attributes
+                    .get("style")
+                    .map(|css| absolutize_css_urls(css, &base_url));
+                if let Some(css) = style {
+                    attributes.insert("style", css);
+                }
+            }
+
+            if element.name.local == local_name!("style") {
+                let css = element.text_contents();
+                let absolutized_css = absolutize_css_urls(&css, &base_url);
+                if absolutized_css != css {
+                    for child in element.as_node().children().collect::<Vec<_>>() {
+                        child.detach();
+                    }
+                    element.as_node().append(NodeRef::new_text(absolutized_css));
+                }
+            }
+        }
+
+        for rule in &mut self.rules.preserved {
+            rule.css = absolutize_css_urls(&rule.css, &base_url);
+            if let Some(ref mut media) = rule.media {
+                for nested_rule in &mut media.rules {
+                    nested_rule.css = absolutize_css_urls(&nested_rule.css, &base_url);
+                }
             }
         }
 
@@ -383,10 +1291,75 @@ impl ApplyTableElementAttributes for Eyeliner {
     }
 }
 
+/// Whether a node is a placeholder for preserved CSS rules: a `<style />` tag with a
+/// `data-eyeliner-preserve` attribute, or an `<!-- eyeliner:preserved -->` comment.
+fn is_preserved_css_placeholder(node: &NodeRef) -> bool {
+    if let Some(element) = node.as_element() {
+        return element.name.local == local_name!("style")
+            && element.attributes.borrow().contains(PRESERVE_ATTRIBUTE);
+    }
+
+    match node.as_comment() {
+        Some(comment) => comment.borrow().trim() == PRESERVE_COMMENT,
+        None => false,
+    }
+}
+
+/// Finds the first placeholder for preserved CSS rules in the HTML document.
+fn find_preserved_css_placeholder(document: &NodeRef) -> Option<NodeRef> {
+    document.descendants().find(is_preserved_css_placeholder)
+}
+
 impl InsertPreservedCss for Eyeliner {
-    /// Tries to insert any `@media` or `@font-face` rules collected into the locations specified
-    // in the HTML document.
+    /// Tries to insert any preserved CSS rules collected into the HTML document, in the same order
+    /// as the CSS stylesheet.
+    ///
+    /// 1.  If the HTML document has a placeholder, preserved CSS rules are added to the
+    ///     `<style data-eyeliner-preserve>` tag, or replace the `<!-- eyeliner:preserved -->`
+    ///     comment with a `<style />` tag.
+    ///
+    /// 2.  Otherwise, a `<style />` tag is added to the first element that matches a selector in
+    ///     `Options.insert_preserved_css`. Nothing is inserted into fragments this way.
+    ///
     fn insert_preserved_css(self: &Self) -> &Self {
+        if self.rules.preserved.is_empty() {
+            return self;
+        }
+
+        if let Some(placeholder) = find_preserved_css_placeholder(&self.document) {
+            let text_node = NodeRef::new_text(self.preserved_css());
+
+            match placeholder.as_element() {
+                Some(element) => {
+                    element.attributes.borrow_mut().remove(PRESERVE_ATTRIBUTE);
+                    if self.options.prepend_preserved_css {
+                        placeholder.prepend(text_node);
+                    } else {
+                        placeholder.append(text_node);
+                    }
+                }
+                None => {
+                    let style_node = NodeRef::new_element(
+                        QualName {
+                            prefix: None,
+                            ns: ns!(html),
+                            local: local_name!("style"),
+                        },
+                        vec![],
+                    );
+                    style_node.append(text_node);
+                    placeholder.insert_before(style_node);
+                    placeholder.detach();
+                }
+            };
+
+            return self;
+        }
+
+        if self.settings.fragment_context_element.is_some() {
+            return self;
+        }
+
         for node_to_insert_style_into in &self.options.insert_preserved_css {
             let mut nodes = match self.document.select(node_to_insert_style_into) {
                 Ok(n) => n,
@@ -398,11 +1371,7 @@ impl InsertPreservedCss for Eyeliner {
                 None => continue,
             };
 
-            let mut preserved_css = vec![];
-            preserved_css.extend_from_slice(&self.rules.font_face);
-            preserved_css.extend_from_slice(&self.rules.media);
-
-            let text_node = NodeRef::new_text(preserved_css.join("\n"));
+            let text_node = NodeRef::new_text(self.preserved_css());
             let style_node = NodeRef::new_element(
                 QualName {
                     prefix: None,
@@ -412,7 +1381,11 @@ impl InsertPreservedCss for Eyeliner {
                 vec![],
             );
             style_node.append(text_node);
-            node.as_node().append(style_node);
+            if self.options.prepend_preserved_css {
+                node.as_node().prepend(style_node);
+            } else {
+                node.as_node().append(style_node);
+            }
             return self;
         }
 
@@ -420,8 +1393,91 @@ impl InsertPreservedCss for Eyeliner {
     }
 }
 
+impl RemovePreservedCssPlaceholders for Eyeliner {
+    /// Removes every `<!-- eyeliner:preserved -->` comment and empty
+    /// `<style data-eyeliner-preserve>` tag left in the HTML document, such as when preserved CSS
+    /// rules are returned separately. A `<style data-eyeliner-preserve>` tag with CSS of its own
+    /// is kept.
+    fn remove_preserved_css_placeholders(self: &Self) -> &Self {
+        let placeholders = self
+            .document
+            .descendants()
+            .filter(is_preserved_css_placeholder)
+            .collect::<Vec<_>>();
+
+        for placeholder in placeholders {
+            if placeholder.as_comment().is_some() || placeholder.text_contents().trim().is_empty() {
+                placeholder.detach();
+            }
+        }
+
+        self
+    }
+}
+
+impl RemoveControlAttributes for Eyeliner {
+    /// Removes the `data-eyeliner-ignore` attribute from every element in the HTML document, and
+    /// the `data-eyeliner-preserve`, `data-embed`, and `data-eyeliner-keep` attributes from
+    /// `<style />` tags.
+    fn remove_control_attributes(self: &Self) -> &Self {
+        for element in self.document.descendants().elements() {
+            let mut attributes = element.attributes.borrow_mut();
+            attributes.remove(IGNORE_ATTRIBUTE);
+
+            if element.name.local == local_name!("style") {
+                for attribute in &STYLE_CONTROL_ATTRIBUTES {
+                    attributes.remove(*attribute);
+                }
+            }
+        }
+
+        self
+    }
+}
+
+impl CheckSizeBudget for Eyeliner {
+    /// Measures the HTML document into `size_report`, given the HTML it was serialized to so it
+    /// isn't serialized again, and adds a warning when it is larger than `Settings.size_budget`.
+    /// `preserved_css` is CSS that isn't in the HTML yet, because the caller inserts it, and is
+    /// counted as if it were.
+    fn check_size_budget(self: &mut Self, html: &str, preserved_css: &str) -> &mut Self {
+        let mut size_report = SizeReport::new(&self.document, html);
+        size_report.total += preserved_css.len();
+        size_report.preserved_css += preserved_css.len();
+
+        if size_report.total > self.settings.size_budget {
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!(
+                    "Inlined HTML is {} bytes, over the size budget of {} bytes ({} bytes of inline \
+                     styles, {} bytes of preserved CSS, and {} bytes of markup)",
+                    size_report.total,
+                    self.settings.size_budget,
+                    size_report.inline_styles,
+                    size_report.preserved_css,
+                    size_report.markup
+                ),
+                location: None,
+                source: None,
+            });
+        }
+
+        self.size_report = Some(size_report);
+        self
+    }
+}
+
 impl ToString for Eyeliner {
+    /// Serializes the HTML document. A fragment is parsed into an `<html>` element, which only
+    /// its children are serialized from.
     fn to_string(self: &Self) -> String {
-        self.document.to_string()
+        if self.settings.fragment_context_element.is_none() {
+            return self.document.to_string();
+        }
+
+        match self.document.first_child() {
+            Some(root) => root.children().map(|child| child.to_string()).collect(),
+            None => String::default(),
+        }
     }
 }
@@ -0,0 +1,176 @@
+use super::{
+    diagnostic::CssSource,
+    error::Error,
+    loader::StylesheetLoader,
+    rules::{PreservedRule, PreservedRuleKind},
+    source_map::JoinedCss,
+};
+use servo_css_parser::cssparser::{
+    serialize_string, Delimiter, ParseError, Parser, ParserInput, Token,
+};
+
+/// Splits the `@import` rules at the start of the CSS from the rest of it.
+///
+/// Returns the URL and media query list of each `@import` rule, and the remaining CSS. Any
+/// `@charset` rule is dropped.
+fn split_imports(css: &str) -> (Vec<(String, String)>, &str) {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+    let mut imports = vec![];
+
+    loop {
+        let start = parser.position();
+        let name = match parser.next() {
+            Ok(Token::AtKeyword(ref name)) => name.to_ascii_lowercase(),
+            Ok(_) => return (imports, &css[start.byte_index()..]),
+            Err(_) => return (imports, ""),
+        };
+
+        match name.as_str() {
+            "charset" => {
+                let _ = parser.parse_until_after(Delimiter::Semicolon, |p| {
+                    while p.next().is_ok() {}
+                    Ok::<_, ParseError<()>>(())
+                });
+            }
+            "import" => {
+                let import = parser.parse_until_after(Delimiter::Semicolon, |p| {
+                    let url = p.expect_url_or_string()?.as_ref().to_owned();
+                    let media_start = p.position();
+                    while p.next().is_ok() {}
+                    Ok::<_, ParseError<()>>((url, p.slice_from(media_start).trim().to_owned()))
+                });
+
+                if let Ok(i) = import {
+                    imports.push(i);
+                }
+            }
+            _ => return (imports, &css[start.byte_index()..]),
+        };
+    }
+}
+
+/// Resolves `href` relative to the `href` of the stylesheet that references it.
+fn join_href(base: Option<&str>, href: &str) -> String {
+    let base = match base {
+        Some(b) if !href.contains("://") && !href.starts_with('/') => b,
+        _ => return href.to_string(),
+    };
+
+    let mut segments = base.split('/').collect::<Vec<_>>();
+    segments.pop();
+
+    for segment in href.split('/') {
+        match segment {
+            "." => (),
+            ".." if !segments.is_empty() && segments.last() != Some(&"..") => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        };
+    }
+
+    segments.join("/")
+}
+
+/// Whether `href` references a stylesheet on another host, such as `https://` or `//` URLs.
+pub(crate) fn is_absolute_href(href: &str) -> bool {
+    href.contains("://") || href.starts_with("//")
+}
+
+/// Serializes an `@import` rule for a stylesheet that is left to be loaded by the email client.
+fn import_rule_css(href: &str, media: &str) -> String {
+    let mut css = "@import ".to_string();
+    let _ = serialize_string(href, &mut css);
+    if !media.is_empty() {
+        css += " ";
+        css += media;
+    }
+
+    css + ";"
+}
+
+/// Replaces the `@import` rules at the start of the CSS from `source` with the CSS they
+/// reference, and appends the result to `joined_css`.
+///
+/// 1.  Each imported stylesheet is loaded with `loader`, relative to the `href` of the
+///     stylesheet that imports it, and has its own `@import` rules resolved.
+///
+/// 2.  Imports with a media query list are wrapped in an `@media` rule.
+///
+/// 3.  Imports of stylesheets on another host that `loader` can't load, or any imports when there
+///     is no `loader`, are kept as `@import` rules in `preserved`, so they can be inserted into the
+///     HTML document with the other preserved CSS rules.
+///
+/// 4.  Other imports that can't be loaded, or that import a stylesheet that is already being
+///     imported, are dropped and recorded in `errors`.
+///
+/// `imported` holds the `href` of each stylesheet that is currently being resolved, starting with
+/// the `href` of the CSS itself, if it has one.
+pub(crate) fn resolve_imports(
+    css: &str,
+    source: &CssSource,
+    loader: Option<&dyn StylesheetLoader>,
+    imported: &mut Vec<String>,
+    preserved: &mut Vec<PreservedRule>,
+    errors: &mut Vec<Error>,
+    joined_css: &mut JoinedCss,
+) {
+    let (imports, rest) = split_imports(css);
+    if imports.is_empty() {
+        joined_css.push(css, 0, source);
+        return;
+    }
+
+    for (url, media) in imports {
+        let href = join_href(imported.last().map(String::as_str), &url);
+
+        if imported.contains(&href) {
+            let mut chain = imported.clone();
+            chain.push(href);
+            errors.push(Error::ImportCycle(chain));
+            continue;
+        }
+
+        let loaded_css = match loader.map(|l| l.load(&href)) {
+            Some(Ok(c)) => c,
+            Some(Err(e)) if !is_absolute_href(&href) => {
+                errors.push(e);
+                continue;
+            }
+            _ => {
+                preserved.push(PreservedRule {
+                    kind: PreservedRuleKind::Import,
+                    css: import_rule_css(&href, &media),
+                    media: None,
+                });
+                continue;
+            }
+        };
+
+        let wrap = !media.is_empty() && !media.eq_ignore_ascii_case("all");
+        if wrap {
+            joined_css.push_generated(&format!("@media {} {{\n", media));
+        }
+
+        let loaded_source = CssSource::Href(href.clone());
+        imported.push(href);
+        resolve_imports(
+            &loaded_css,
+            &loaded_source,
+            loader,
+            imported,
+            preserved,
+            errors,
+            joined_css,
+        );
+        imported.pop();
+
+        if wrap {
+            joined_css.push_generated("\n}");
+        }
+        joined_css.push_generated("\n");
+    }
+
+    joined_css.push(css, css.len() - rest.len(), source);
+}
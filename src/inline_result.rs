@@ -0,0 +1,15 @@
+use super::diagnostic::Diagnostic;
+
+/// The output of inlining, for callers that decide where preserved CSS rules go themselves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InlineResult {
+    /// The HTML with CSS inlined. Preserved CSS rules aren't inserted into it, and their
+    /// placeholders are removed.
+    pub html: String,
+
+    /// Preserved CSS rules, such as `@media` rules, in the same order as the CSS stylesheet.
+    pub preserved_css: String,
+
+    /// Problems found while inlining, such as CSS that could not be parsed or was ignored.
+    pub diagnostics: Vec<Diagnostic>,
+}
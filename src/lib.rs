@@ -1,14 +1,33 @@
 //! A CSS inliner for making emails.
 
+#[cfg(feature = "config")]
+mod config;
+mod diagnostic;
+mod error;
 mod eyeliner;
 mod hash;
+mod imports;
+mod inline_result;
+mod loader;
+mod minify;
 mod options;
+mod pseudo_elements;
 mod rules;
+mod selector;
 mod settings;
+mod size_report;
+mod source_map;
+mod stylesheet;
 pub mod traits;
+mod urls;
 
+#[cfg(feature = "config")]
+pub use self::config::*;
 use self::traits::*;
-pub use self::{eyeliner::*, hash::*, options::*, rules::*, settings::*};
+pub use self::{
+    diagnostic::*, error::*, eyeliner::*, hash::*, inline_result::*, loader::*, options::*,
+    rules::*, settings::*, size_report::*, source_map::*, stylesheet::*,
+};
 pub use servo_css_parser::{servo_config, embedder_traits as servo_embedder_traits};
 
 /// Returns a string of HTML with CSS inlined.
@@ -77,12 +96,331 @@ pub fn inline(
     options: Option<AbstractOptions>,
     settings: Option<AbstractSettings>,
 ) -> String {
-    Eyeliner::new(html, css, options, settings)
+    inline_document(&mut Eyeliner::new(html, css, options, settings))
+}
+
+/// Returns a string of HTML with CSS inlined, or an error instead of ignoring invalid input.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline`.
+///
+/// # Remarks
+///
+/// Any CSS that can't be parsed, invalid selectors in `Options.insert_preserved_css`, and unknown
+/// CSS properties in `Settings` are returned as an `Error`. So is HTML that is larger than
+/// `Settings.size_budget`, when `Options.enforce_size_budget` is `true`.
+///
+/// # Examples
+///
+/// ```
+///   use eyeliner::{try_inline, AbstractSettings, Error};
+///
+///   let settings = AbstractSettings {
+///     excluded_properties: Some(vec!["colour".to_owned()]),
+///     ..AbstractSettings::default()
+///   };
+///
+///   assert_eq!(
+///     try_inline("<p>Hello, world!</p>", None, None, Some(settings)),
+///     Err(Error::UnknownProperty("colour".to_owned())),
+///   );
+/// ```
+pub fn try_inline(
+    html: &str,
+    css: Option<String>,
+    options: Option<AbstractOptions>,
+    settings: Option<AbstractSettings>,
+) -> Result<String, Error> {
+    try_inline_document(&mut Eyeliner::try_new(html, css, options, settings)?)
+}
+
+/// Returns a fragment of HTML with CSS inlined, without wrapping it in `<html>`, `<head>`, and
+/// `<body>` tags.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline`.
+///
+/// # Remarks
+///
+/// The fragment is parsed as the contents of `Settings.fragment_context_element`, or of a `<body>`
+/// element if it isn't set. Preserved CSS rules, such as `@media` rules, are left out unless the
+/// fragment has a placeholder for them. Use `Eyeliner::preserved_css` to get them.
+///
+/// # Examples
+///
+/// ```
+///   use eyeliner::inline_fragment;
+///
+///   let html = r#"<p class="red">Hello, world!</p>"#;
+///   let css = ".red { color: red; }";
+///
+///   assert_eq!(
+///     inline_fragment(html, Some(css.to_owned()), None, None),
+///     r#"<p class="red" style="color: red;">Hello, world!</p>"#,
+///   );
+/// ```
+pub fn inline_fragment(
+    html: &str,
+    css: Option<String>,
+    options: Option<AbstractOptions>,
+    settings: Option<AbstractSettings>,
+) -> String {
+    inline_document(&mut Eyeliner::new(
+        html,
+        css,
+        options,
+        Some(into_fragment_settings(settings)),
+    ))
+}
+
+/// Returns a fragment of HTML with CSS inlined, or an error instead of ignoring invalid input.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline`, and returns the same errors as `try_inline`.
+pub fn try_inline_fragment(
+    html: &str,
+    css: Option<String>,
+    options: Option<AbstractOptions>,
+    settings: Option<AbstractSettings>,
+) -> Result<String, Error> {
+    try_inline_document(&mut Eyeliner::try_new(
+        html,
+        css,
+        options,
+        Some(into_fragment_settings(settings)),
+    )?)
+}
+
+/// Returns a string of HTML with the CSS of a compiled stylesheet inlined.
+///
+/// # Arguments
+///
+/// *   `html` - A string of HTML to have CSS inlined into. Any `<style />` tags will have their
+///     styles parsed and processed, after the rules of the compiled stylesheet.
+///
+/// *   `compiled_stylesheet` - A `CompiledStylesheet`, whose options and settings are used.
+///
+/// # Remarks
+///
+/// The CSS of the compiled stylesheet is only parsed once, which makes inlining many HTML
+/// documents with the same CSS faster.
+///
+/// # Examples
+///
+/// ```
+///   use eyeliner::{inline, inline_compiled, AbstractOptions, AbstractSettings, CompiledStylesheet};
+///
+///   let css = r#"
+///     .red {
+///       color: red;
+///     }
+///   "#;
+///
+///   let compiled_stylesheet =
+///     CompiledStylesheet::new(css, None::<AbstractOptions>, None::<AbstractSettings>);
+///
+///   for html in &[r#"<p class="red">Hello</p>"#, r#"<p class="red">World</p>"#] {
+///     assert_eq!(
+///       inline_compiled(html, &compiled_stylesheet),
+///       inline(html, Some(css.to_owned()), None, None),
+///     );
+///   }
+/// ```
+pub fn inline_compiled(html: &str, compiled_stylesheet: &CompiledStylesheet) -> String {
+    inline_document(&mut Eyeliner::from_compiled(html, compiled_stylesheet))
+}
+
+/// Returns a string of HTML with the CSS of a compiled stylesheet inlined, or an error if any CSS
+/// in the HTML can't be parsed or loaded.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline_compiled`.
+pub fn try_inline_compiled(
+    html: &str,
+    compiled_stylesheet: &CompiledStylesheet,
+) -> Result<String, Error> {
+    try_inline_document(&mut Eyeliner::try_from_compiled(html, compiled_stylesheet)?)
+}
+
+/// Returns HTML with CSS inlined, along with the preserved CSS rules and diagnostics, instead of
+/// inserting the preserved CSS rules into the HTML.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline`.
+///
+/// # Remarks
+///
+/// Useful for placing preserved CSS rules, such as `@media` rules, somewhere that
+/// `Options.insert_preserved_css` can't, like inside a conditional comment.
+///
+/// # Examples
+///
+/// ```
+///   use eyeliner::inline_result;
+///
+///   let html = r#"<p class="red">Hello, world!</p>"#;
+///   let css = r#"
+///     .red { color: red; }
+///     @media (max-width: 600px) { .red { color: blue; } }
+///   "#;
+///
+///   let result = inline_result(html, Some(css.to_owned()), None, None);
+///   assert!(result.html.contains(r#"<p class="red" style="color: red;">"#));
+///   assert!(!result.html.contains("<style>"));
+///   assert!(result.preserved_css.contains("@media (max-width: 600px)"));
+/// ```
+pub fn inline_result(
+    html: &str,
+    css: Option<String>,
+    options: Option<AbstractOptions>,
+    settings: Option<AbstractSettings>,
+) -> InlineResult {
+    inline_document_result(&mut Eyeliner::new(html, css, options, settings))
+}
+
+/// Returns HTML with CSS inlined, along with the preserved CSS rules and diagnostics, or an error
+/// instead of ignoring invalid input.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline`, and returns the same errors as `try_inline`.
+pub fn try_inline_result(
+    html: &str,
+    css: Option<String>,
+    options: Option<AbstractOptions>,
+    settings: Option<AbstractSettings>,
+) -> Result<InlineResult, Error> {
+    try_inline_document_result(&mut Eyeliner::try_new(html, css, options, settings)?)
+}
+
+/// Returns HTML with the CSS of a compiled stylesheet inlined, along with the preserved CSS rules
+/// and diagnostics.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline_compiled`.
+pub fn inline_compiled_result(
+    html: &str,
+    compiled_stylesheet: &CompiledStylesheet,
+) -> InlineResult {
+    inline_document_result(&mut Eyeliner::from_compiled(html, compiled_stylesheet))
+}
+
+/// Returns HTML with the CSS of a compiled stylesheet inlined, along with the preserved CSS rules
+/// and diagnostics, or an error if any CSS in the HTML can't be parsed or loaded.
+///
+/// # Arguments
+///
+/// Takes the same arguments as `inline_compiled`.
+pub fn try_inline_compiled_result(
+    html: &str,
+    compiled_stylesheet: &CompiledStylesheet,
+) -> Result<InlineResult, Error> {
+    try_inline_document_result(&mut Eyeliner::try_from_compiled(html, compiled_stylesheet)?)
+}
+
+/// Sets `fragment_context_element` to `body` in settings that don't have one.
+fn into_fragment_settings(settings: Option<AbstractSettings>) -> AbstractSettings {
+    let settings = settings.unwrap_or_default();
+
+    AbstractSettings {
+        fragment_context_element: settings
+            .fragment_context_element
+            .or_else(|| Some(String::from("body"))),
+        ..settings
+    }
+}
+
+/// Runs every step of inlining on an instance that modifies the HTML document, except for
+/// inserting preserved CSS rules.
+fn apply_steps(eyeliner: &mut Eyeliner) {
+    eyeliner
         .collect_rules()
         .apply_rules()
+        .apply_pseudo_elements()
+        .prune_media_queries()
         .apply_width_attributes()
         .apply_height_attributes()
-        .apply_table_element_attributes()
+        .apply_table_element_attributes();
+    eyeliner.absolutize_urls();
+}
+
+/// Runs every step of inlining on an instance and returns the HTML.
+///
+/// # Remarks
+///
+/// Useful for reading the diagnostics of the instance afterwards, such as CSS in the HTML that
+/// could not be parsed or a warning about `Settings.size_budget`, while still inserting preserved
+/// CSS rules into the HTML.
+pub fn inline_document(eyeliner: &mut Eyeliner) -> String {
+    apply_steps(eyeliner);
+    eyeliner
         .insert_preserved_css()
-        .to_string()
+        .remove_preserved_css_placeholders()
+        .remove_control_attributes();
+
+    let html = eyeliner.to_string();
+    eyeliner.check_size_budget(&html, "");
+
+    html
+}
+
+/// Runs every step of inlining on an instance, except for inserting preserved CSS rules, and
+/// returns the HTML, preserved CSS rules, and diagnostics. Placeholders for preserved CSS rules
+/// are removed from the HTML, since the caller decides where they go.
+fn inline_document_result(eyeliner: &mut Eyeliner) -> InlineResult {
+    apply_steps(eyeliner);
+    eyeliner
+        .remove_preserved_css_placeholders()
+        .remove_control_attributes();
+    let html = eyeliner.to_string();
+    let preserved_css = eyeliner.preserved_css();
+    eyeliner.check_size_budget(&html, &preserved_css);
+
+    InlineResult {
+        html,
+        preserved_css,
+        diagnostics: eyeliner.diagnostics.clone(),
+    }
+}
+
+/// Returns an error if `Options.enforce_size_budget` is set and the HTML of an instance that was
+/// inlined is over `Settings.size_budget`.
+fn enforce_size_budget(eyeliner: &Eyeliner) -> Result<(), Error> {
+    match eyeliner.size_report {
+        Some(size_report)
+            if eyeliner.options.enforce_size_budget
+                && size_report.total > eyeliner.settings.size_budget =>
+        {
+            Err(Error::SizeBudgetExceeded {
+                size: size_report.total,
+                budget: eyeliner.settings.size_budget,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs every step of inlining on an instance and returns the HTML, or an error if the HTML is
+/// over the size budget.
+pub fn try_inline_document(eyeliner: &mut Eyeliner) -> Result<String, Error> {
+    let html = inline_document(eyeliner);
+    enforce_size_budget(eyeliner)?;
+
+    Ok(html)
+}
+
+/// Runs every step of inlining on an instance, except for inserting preserved CSS rules, and
+/// returns the HTML, preserved CSS rules, and diagnostics, or an error if the HTML is over the
+/// size budget.
+fn try_inline_document_result(eyeliner: &mut Eyeliner) -> Result<InlineResult, Error> {
+    let inline_result = inline_document_result(eyeliner);
+    enforce_size_budget(eyeliner)?;
+
+    Ok(inline_result)
 }
@@ -0,0 +1,77 @@
+use super::error::Error;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Loads the CSS of external stylesheets.
+pub trait StylesheetLoader: Debug + Send + Sync {
+    /// Returns the CSS of the stylesheet referenced by `href`.
+    fn load(self: &Self, href: &str) -> Result<String, Error>;
+}
+
+/// Loads stylesheets from files in a root directory.
+///
+/// Only relative paths are loaded, and they can't leave the root directory. Any query string or
+/// fragment in `href` is ignored.
+#[derive(Clone, Debug)]
+pub struct FileSystemLoader {
+    /// The directory that paths are resolved against.
+    pub root: PathBuf,
+}
+
+impl FileSystemLoader {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl StylesheetLoader for FileSystemLoader {
+    fn load(self: &Self, href: &str) -> Result<String, Error> {
+        let load_error = |message: &str| Error::Load {
+            href: href.to_string(),
+            message: message.to_string(),
+        };
+
+        let path = href.split(&['?', '#'][..]).next().unwrap_or("");
+        if path.contains("://") || path.starts_with("//") {
+            return Err(load_error(
+                "Only relative paths can be loaded from the file system",
+            ));
+        }
+
+        let path = Path::new(path.trim_start_matches('/'));
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err(load_error("Paths can't leave the root directory"));
+        }
+
+        fs::read_to_string(self.root.join(path)).map_err(|e| load_error(&e.to_string()))
+    }
+}
+
+/// Loads stylesheets from memory, using `href` as the key.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryLoader {
+    /// Map of `href` to CSS.
+    pub stylesheets: HashMap<String, String>,
+}
+
+impl MemoryLoader {
+    pub fn new(stylesheets: HashMap<String, String>) -> Self {
+        Self { stylesheets }
+    }
+}
+
+impl StylesheetLoader for MemoryLoader {
+    fn load(self: &Self, href: &str) -> Result<String, Error> {
+        self.stylesheets
+            .get(href)
+            .cloned()
+            .ok_or_else(|| Error::Load {
+                href: href.to_string(),
+                message: "Stylesheet not found".to_string(),
+            })
+    }
+}
@@ -0,0 +1,337 @@
+use servo_css_parser::cssparser::{ParseError, Parser, ParserInput, Token};
+
+/// Colors with a name that is shorter than their shortest hex form.
+const COLOR_NAMES: &[(&str, &str)] = &[
+    ("#000080", "navy"),
+    ("#008000", "green"),
+    ("#008080", "teal"),
+    ("#4b0082", "indigo"),
+    ("#800000", "maroon"),
+    ("#800080", "purple"),
+    ("#808000", "olive"),
+    ("#808080", "gray"),
+    ("#a0522d", "sienna"),
+    ("#a52a2a", "brown"),
+    ("#c0c0c0", "silver"),
+    ("#cd853f", "peru"),
+    ("#d2b48c", "tan"),
+    ("#da70d6", "orchid"),
+    ("#dda0dd", "plum"),
+    ("#ee82ee", "violet"),
+    ("#f00", "red"),
+    ("#f0e68c", "khaki"),
+    ("#f0ffff", "azure"),
+    ("#f5deb3", "wheat"),
+    ("#f5f5dc", "beige"),
+    ("#fa8072", "salmon"),
+    ("#faf0e6", "linen"),
+    ("#ff6347", "tomato"),
+    ("#ff7f50", "coral"),
+    ("#ffa500", "orange"),
+    ("#ffc0cb", "pink"),
+    ("#ffd700", "gold"),
+    ("#ffe4c4", "bisque"),
+    ("#fffafa", "snow"),
+    ("#fffff0", "ivory"),
+];
+
+/// Length units that can be dropped from a zero.
+const LENGTH_UNITS: &[&str] = &[
+    "ch", "cm", "em", "ex", "in", "mm", "pc", "pt", "px", "q", "rem", "vh", "vmax", "vmin", "vw",
+];
+
+/// Where component values are being minified, which decides the whitespace that is significant
+/// and the values that can be shortened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Context {
+    /// A selector or an at-rule prelude, where whitespace can be a descendant combinator.
+    Prelude,
+
+    /// Declarations, where colors and zero lengths can be shortened.
+    Declarations,
+
+    /// The arguments of a function in declarations, such as `calc()`, where units are kept.
+    Function,
+
+    /// The value of a custom property, such as `--gap: 0px`, which is copied as written since it
+    /// can be used anywhere, such as in `calc()`.
+    CustomProperty,
+}
+
+/// Minifies a CSS stylesheet by removing comments, optional whitespace, and trailing semicolons,
+/// and by shortening the values of declarations.
+pub fn minify_stylesheet(css: &str) -> String {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+    let mut minified_css = String::default();
+    let mut prelude_start = parser.position();
+
+    loop {
+        let token = match parser.next() {
+            Ok(t) => t.clone(),
+            Err(_) => break,
+        };
+
+        match token {
+            Token::CurlyBracketBlock => {
+                let prelude = parser.slice_from(prelude_start);
+                minified_css.push_str(&minify_prelude(&prelude[..prelude.len() - 1]));
+
+                let block = parser
+                    .parse_nested_block(|p| {
+                        let start = p.position();
+                        while p.next().is_ok() {}
+                        Ok::<_, ParseError<()>>(p.slice_from(start))
+                    })
+                    .unwrap_or_default();
+
+                // A block of rules, such as `@media`, has blocks of its own.
+                minified_css.push('{');
+                if has_blocks(block) {
+                    minified_css.push_str(&minify_stylesheet(block));
+                } else {
+                    minified_css.push_str(&minify_declarations(block));
+                }
+                minified_css.push('}');
+
+                prelude_start = parser.position();
+            }
+            Token::Semicolon => {
+                let prelude = parser.slice_from(prelude_start);
+                minified_css.push_str(&minify_prelude(&prelude[..prelude.len() - 1]));
+                minified_css.push(';');
+
+                prelude_start = parser.position();
+            }
+            _ => (),
+        };
+    }
+
+    minified_css.push_str(&minify_prelude(parser.slice_from(prelude_start)));
+    minified_css
+}
+
+/// Minifies a list of declarations, such as the value of a `style` attribute.
+pub fn minify_declarations(css: &str) -> String {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+
+    minify_component_values(&mut parser, Context::Declarations)
+        .trim_end_matches(';')
+        .to_string()
+}
+
+/// Minifies a selector or an at-rule prelude.
+fn minify_prelude(css: &str) -> String {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+
+    minify_component_values(&mut parser, Context::Prelude)
+}
+
+/// Returns whether `css` contains a `{}` block outside of any other block.
+fn has_blocks(css: &str) -> bool {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+
+    while let Ok(token) = parser.next() {
+        if let Token::CurlyBracketBlock = token {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Returns whether whitespace before `token` can be removed.
+fn drops_whitespace_before(token: &Token, context: Context) -> bool {
+    match *token {
+        Token::Comma | Token::Semicolon | Token::Delim('!') => true,
+        Token::Colon => context != Context::Prelude,
+        Token::Delim('>') | Token::Delim('+') | Token::Delim('~') => context == Context::Prelude,
+        Token::Delim('/') => context != Context::Prelude,
+        _ => false,
+    }
+}
+
+/// Returns whether whitespace after `token` can be removed.
+fn drops_whitespace_after(token: &Token, context: Context) -> bool {
+    match *token {
+        Token::Comma | Token::Semicolon | Token::Colon | Token::Delim('!') => true,
+        Token::Delim('>') | Token::Delim('+') | Token::Delim('~') => context == Context::Prelude,
+        Token::Delim('/') => context != Context::Prelude,
+        _ => false,
+    }
+}
+
+/// Minifies the component values left in `parser`, including nested blocks.
+///
+/// Tokens that aren't shortened are copied as written, since serializing them again can change
+/// them, such as the numbers that `U+1E00-1EFF` in `unicode-range` is split into.
+fn minify_component_values(parser: &mut Parser, context: Context) -> String {
+    let mut minified_css = String::default();
+    let mut whitespace = false;
+    let mut previous_token: Option<Token> = None;
+    let mut value_context = context;
+
+    loop {
+        let start = parser.position();
+        let token = match parser.next_including_whitespace_and_comments() {
+            Ok(t) => t.clone(),
+            Err(_) => break,
+        };
+
+        match token {
+            Token::WhiteSpace(_) => {
+                whitespace = true;
+                continue;
+            }
+            Token::Comment(_) => continue,
+            _ => (),
+        };
+
+        if context == Context::Declarations {
+            match token {
+                Token::Ident(ref name)
+                    if name.starts_with("--")
+                        && matches!(previous_token, None | Some(Token::Semicolon)) =>
+                {
+                    value_context = Context::CustomProperty
+                }
+                Token::Semicolon => value_context = Context::Declarations,
+                _ => (),
+            };
+        }
+
+        let keeps_whitespace = match previous_token {
+            Some(ref t) => {
+                !drops_whitespace_after(t, value_context)
+                    && !drops_whitespace_before(&token, value_context)
+            }
+            None => false,
+        };
+        if whitespace && keeps_whitespace {
+            minified_css.push(' ');
+        }
+        whitespace = false;
+
+        let nested_context = match value_context {
+            Context::Prelude | Context::CustomProperty => value_context,
+            _ => Context::Function,
+        };
+        let shortens_values =
+            value_context == Context::Declarations || value_context == Context::Function;
+
+        match token {
+            Token::Hash(ref value) | Token::IDHash(ref value) if shortens_values => {
+                match parse_hex_color(value) {
+                    Some((r, g, b)) => minified_css.push_str(&shortest_color(r, g, b)),
+                    None => minified_css.push_str(parser.slice_from(start)),
+                };
+            }
+            Token::Dimension {
+                value, ref unit, ..
+            } if value == 0.0
+                && value_context == Context::Declarations
+                && LENGTH_UNITS.contains(&unit.to_ascii_lowercase().as_str()) =>
+            {
+                minified_css.push('0');
+            }
+            Token::Function(ref name) => {
+                if shortens_values && name.eq_ignore_ascii_case("rgb") {
+                    let state = parser.state();
+                    if let Ok((r, g, b)) = parser.parse_nested_block(parse_rgb_arguments) {
+                        minified_css.push_str(&shortest_color(r, g, b));
+                        previous_token = Some(token);
+                        continue;
+                    }
+                    parser.reset(&state);
+                }
+
+                let arguments = parser
+                    .parse_nested_block(|p| {
+                        Ok::<_, ParseError<()>>(minify_component_values(p, nested_context))
+                    })
+                    .unwrap_or_default();
+                minified_css.push_str(&format!("{}({})", name, arguments));
+            }
+            Token::ParenthesisBlock | Token::SquareBracketBlock | Token::CurlyBracketBlock => {
+                let (open, close) = match token {
+                    Token::ParenthesisBlock => ('(', ')'),
+                    Token::SquareBracketBlock => ('[', ']'),
+                    _ => ('{', '}'),
+                };
+
+                let contents = parser
+                    .parse_nested_block(|p| {
+                        Ok::<_, ParseError<()>>(minify_component_values(p, nested_context))
+                    })
+                    .unwrap_or_default();
+                minified_css.push(open);
+                minified_css.push_str(&contents);
+                minified_css.push(close);
+            }
+            _ => minified_css.push_str(parser.slice_from(start)),
+        };
+
+        previous_token = Some(token);
+    }
+
+    minified_css
+}
+
+/// Parses the arguments of an `rgb()` function with three integer channels.
+fn parse_rgb_arguments<'i, 't>(
+    parser: &mut Parser<'i, 't>,
+) -> Result<(u8, u8, u8), ParseError<'i, ()>> {
+    let mut channels = [0; 3];
+
+    for (i, channel) in channels.iter_mut().enumerate() {
+        if i > 0 {
+            parser.expect_comma()?;
+        }
+
+        let location = parser.current_source_location();
+        match *parser.next()? {
+            Token::Number {
+                int_value: Some(v), ..
+            } if (0..=255).contains(&v) => *channel = v as u8,
+            ref t => return Err(location.new_unexpected_token_error(t.clone())),
+        };
+    }
+
+    parser.expect_exhausted()?;
+    Ok((channels[0], channels[1], channels[2]))
+}
+
+/// Parses a 3 or 6 digit hex color, without the `#`.
+fn parse_hex_color(value: &str) -> Option<(u8, u8, u8)> {
+    let digits = value
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<_>>>()?;
+
+    match digits.len() {
+        3 => Some((digits[0] * 17, digits[1] * 17, digits[2] * 17)),
+        6 => Some((
+            digits[0] * 16 + digits[1],
+            digits[2] * 16 + digits[3],
+            digits[4] * 16 + digits[5],
+        )),
+        _ => None,
+    }
+}
+
+/// Returns the shortest way to write an opaque color.
+fn shortest_color(r: u8, g: u8, b: u8) -> String {
+    let hex = if [r, g, b].iter().all(|c| c >> 4 == c & 0x0f) {
+        format!("#{:x}{:x}{:x}", r / 17, g / 17, b / 17)
+    } else {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    };
+
+    match COLOR_NAMES.iter().find(|(h, _)| *h == hex) {
+        Some((_, name)) => name.to_string(),
+        None => hex,
+    }
+}
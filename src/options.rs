@@ -1,59 +1,221 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// Options for ways to modify the HTML document using CSS.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct AbstractOptions {
+    /// Whether to resolve relative URLs in `src`, `href`, and `background` attributes and in CSS
+    /// `url()` values against the `href` of a `<base>` tag, or `Settings.base_url`, and then remove
+    /// `<base>` tags. URLs that are only a `#fragment`, or that have a template tag such as
+    /// `{{unsubscribe}}` or `*|UNSUB|*`, are left as they are.
+    /// Defaults to `false`.
+    pub absolutize_urls: Option<bool>,
+
     /// Whether attributes specified in `Settings.style_to_attribute` get applied to table
     /// elements in `Settings.table_elements`.
     /// Defaults to `true`.
     pub apply_table_element_attributes: Option<bool>,
 
-    /// Whether to use any CSS pixel heights to create `height` attributes on elements set in
-    /// `Settings.height_elements`.
+    /// Whether to use any CSS heights to create `height` attributes on elements set in
+    /// `Settings.height_elements`. `max-height` is used when there is no usable `height`, and
+    /// `auto` removes any existing `height` attribute.
     /// Defaults to `true`.
     pub apply_height_attributes: Option<bool>,
 
+    /// Whether to inline CSS from `<link rel="stylesheet" />` tags in the HTML document, using
+    /// `Settings.stylesheet_loader`. Tags with a `data-eyeliner-ignore` attribute are skipped, and
+    /// tags for stylesheets on another host that the loader can't load are left as they are.
+    /// Defaults to `true`.
+    pub apply_link_tags: Option<bool>,
+
+    /// Whether to turn `::before` and `::after` rules into `<span>` elements inserted as the first
+    /// and last children of the elements they match, since most email clients don't support
+    /// generated content. The `content` property becomes the text of the `<span>`, and the
+    /// remaining declarations are inlined onto it.
+    /// Defaults to `false`.
+    pub apply_pseudo_elements: Option<bool>,
+
     /// Whether to inline CSS in `<style />` tags in the HTML document.
     /// Defaults to `true`.
     pub apply_style_tags: Option<bool>,
 
-    /// Whether to use any CSS pixel widths to create `width` attributes on elements set in
-    /// `Settings.width_elements`.
+    /// Whether to use any CSS widths to create `width` attributes on elements set in
+    /// `Settings.width_elements`. `max-width` is used when there is no usable `width`, and `auto`
+    /// removes any existing `width` attribute.
     /// Defaults to `true`.
     pub apply_width_attributes: Option<bool>,
 
-    /// List of elements to try to inline preserved `@media` and `@font-face` CSS rules into.
-    /// Give an empty list to prevent inlining preserved CSS.
+    /// Whether the `try_*` functions return an error, instead of adding a warning to `diagnostics`,
+    /// when the inlined HTML document is larger than `Settings.size_budget`.
+    /// Defaults to `false`.
+    pub enforce_size_budget: Option<bool>,
+
+    /// Whether to write every longhand CSS property in `style` attributes, such as `padding-top`,
+    /// instead of collapsing longhands into their shorthand, such as `padding`, when all of them
+    /// are set.
+    /// Defaults to `false`.
+    pub expand_shorthands: Option<bool>,
+
+    /// Whether declarations already in an element's `style` attribute take precedence over
+    /// non-`!important` CSS rules, like they do in browsers. When `false`, CSS rules are applied
+    /// on top of the `style` attribute.
+    /// Defaults to `true`.
+    pub inline_style_precedence: Option<bool>,
+
+    /// List of elements to try to inline preserved CSS rules, such as `@media` rules, into.
+    /// Give an empty list to prevent inlining preserved CSS. A `<style data-eyeliner-preserve>`
+    /// element or an `<!-- eyeliner:preserved -->` comment in the HTML document is used instead,
+    /// if there is one.
     /// Defaults to `["head", "body", "html"]`.
     pub insert_preserved_css: Option<Vec<String>>,
 
+    /// Whether to minify the CSS of `style` attributes and preserved CSS rules, by removing
+    /// optional whitespace and trailing semicolons, and by shortening colors and zero lengths.
+    /// The values of custom properties, such as `--gap: 0px`, are never shortened.
+    /// Defaults to `false`.
+    pub minify: Option<bool>,
+
+    /// Whether to insert preserved CSS rules as the first child of the element or placeholder they
+    /// are inserted into, instead of the last.
+    /// Defaults to `false`.
+    pub prepend_preserved_css: Option<bool>,
+
     /// Whether to preserve `@font-face` CSS rules.
     /// Defaults to `true`.
     pub preserve_font_faces: Option<bool>,
 
+    /// Whether to preserve `@font-feature-values` CSS rules.
+    /// Defaults to `true`.
+    pub preserve_font_feature_values: Option<bool>,
+
     /// Whether to preserve `!important` in CSS rules.
     /// Defaults to `false`.
     pub preserve_important: Option<bool>,
 
+    /// Whether to preserve `@keyframes` CSS rules.
+    /// Defaults to `true`.
+    pub preserve_keyframes: Option<bool>,
+
     /// Whether to preserve `@media` CSS rules.
     /// Defaults to `true`.
     pub preserve_media_queries: Option<bool>,
 
+    /// Whether to preserve `@page` CSS rules.
+    /// Defaults to `true`.
+    pub preserve_pages: Option<bool>,
+
+    /// Whether to keep style rules with pseudo-classes in `Settings.stateful_pseudo_classes`, such
+    /// as `a:hover`, which can't be inlined, and insert them with the other preserved CSS.
+    /// Defaults to `true`.
+    pub preserve_pseudo_classes: Option<bool>,
+
+    /// Whether to preserve `@supports` CSS rules.
+    /// Defaults to `true`.
+    pub preserve_supports: Option<bool>,
+
+    /// Whether to prune preserved `@media` rules down to the style rules with selectors that match
+    /// the HTML document, and remove `@media` rules that end up empty. Stateful pseudo-classes and
+    /// `::before` and `::after` pseudo-elements are ignored when matching, so rules such as
+    /// `a:hover` are kept if there is an `<a>` element.
+    /// Defaults to `false`.
+    pub prune_media_queries: Option<bool>,
+
+    /// Whether to remove `<link rel="stylesheet" />` tags, after their CSS has been loaded.
+    /// Defaults to `true`.
+    pub remove_link_tags: Option<bool>,
+
     /// Whether to remove `<style />` tags, after they have optionally had their CSS extracted.
     /// Defaults to `true`.
     pub remove_style_tags: Option<bool>,
+
+    /// Whether to sort the declarations in each `style` attribute by property name, so the same
+    /// styles are always written the same way. A shorthand takes the place of its first longhand.
+    /// Defaults to `false`.
+    pub sort_declarations: Option<bool>,
 }
 
 impl Default for AbstractOptions {
     fn default() -> Self {
         Self {
+            absolutize_urls: None,
             apply_table_element_attributes: None,
             apply_height_attributes: None,
+            apply_link_tags: None,
+            apply_pseudo_elements: None,
             apply_style_tags: None,
             apply_width_attributes: None,
+            enforce_size_budget: None,
+            expand_shorthands: None,
+            inline_style_precedence: None,
             insert_preserved_css: None,
+            minify: None,
+            prepend_preserved_css: None,
             preserve_font_faces: None,
+            preserve_font_feature_values: None,
             preserve_important: None,
+            preserve_keyframes: None,
             preserve_media_queries: None,
+            preserve_pages: None,
+            preserve_pseudo_classes: None,
+            preserve_supports: None,
+            prune_media_queries: None,
+            remove_link_tags: None,
             remove_style_tags: None,
+            sort_declarations: None,
+        }
+    }
+}
+
+impl AbstractOptions {
+    /// Returns these options with every field that is set in `overrides` replaced by the
+    /// override.
+    pub fn merge(self, overrides: Self) -> Self {
+        Self {
+            absolutize_urls: overrides.absolutize_urls.or(self.absolutize_urls),
+            apply_table_element_attributes: overrides
+                .apply_table_element_attributes
+                .or(self.apply_table_element_attributes),
+            apply_height_attributes: overrides
+                .apply_height_attributes
+                .or(self.apply_height_attributes),
+            apply_link_tags: overrides.apply_link_tags.or(self.apply_link_tags),
+            apply_pseudo_elements: overrides
+                .apply_pseudo_elements
+                .or(self.apply_pseudo_elements),
+            apply_style_tags: overrides.apply_style_tags.or(self.apply_style_tags),
+            apply_width_attributes: overrides
+                .apply_width_attributes
+                .or(self.apply_width_attributes),
+            enforce_size_budget: overrides.enforce_size_budget.or(self.enforce_size_budget),
+            expand_shorthands: overrides.expand_shorthands.or(self.expand_shorthands),
+            inline_style_precedence: overrides
+                .inline_style_precedence
+                .or(self.inline_style_precedence),
+            insert_preserved_css: overrides.insert_preserved_css.or(self.insert_preserved_css),
+            minify: overrides.minify.or(self.minify),
+            prepend_preserved_css: overrides
+                .prepend_preserved_css
+                .or(self.prepend_preserved_css),
+            preserve_font_faces: overrides.preserve_font_faces.or(self.preserve_font_faces),
+            preserve_font_feature_values: overrides
+                .preserve_font_feature_values
+                .or(self.preserve_font_feature_values),
+            preserve_important: overrides.preserve_important.or(self.preserve_important),
+            preserve_keyframes: overrides.preserve_keyframes.or(self.preserve_keyframes),
+            preserve_media_queries: overrides
+                .preserve_media_queries
+                .or(self.preserve_media_queries),
+            preserve_pages: overrides.preserve_pages.or(self.preserve_pages),
+            preserve_pseudo_classes: overrides
+                .preserve_pseudo_classes
+                .or(self.preserve_pseudo_classes),
+            preserve_supports: overrides.preserve_supports.or(self.preserve_supports),
+            prune_media_queries: overrides.prune_media_queries.or(self.prune_media_queries),
+            remove_link_tags: overrides.remove_link_tags.or(self.remove_link_tags),
+            remove_style_tags: overrides.remove_style_tags.or(self.remove_style_tags),
+            sort_declarations: overrides.sort_declarations.or(self.sort_declarations),
         }
     }
 }
@@ -61,15 +223,31 @@ impl Default for AbstractOptions {
 impl From<ConcreteOptions> for AbstractOptions {
     fn from(concrete_options: ConcreteOptions) -> Self {
         AbstractOptions {
+            absolutize_urls: Some(concrete_options.absolutize_urls),
             apply_table_element_attributes: Some(concrete_options.apply_table_element_attributes),
             apply_height_attributes: Some(concrete_options.apply_height_attributes),
+            apply_link_tags: Some(concrete_options.apply_link_tags),
+            apply_pseudo_elements: Some(concrete_options.apply_pseudo_elements),
             apply_style_tags: Some(concrete_options.apply_style_tags),
             apply_width_attributes: Some(concrete_options.apply_width_attributes),
+            enforce_size_budget: Some(concrete_options.enforce_size_budget),
+            expand_shorthands: Some(concrete_options.expand_shorthands),
+            inline_style_precedence: Some(concrete_options.inline_style_precedence),
             insert_preserved_css: Some(concrete_options.insert_preserved_css),
+            minify: Some(concrete_options.minify),
+            prepend_preserved_css: Some(concrete_options.prepend_preserved_css),
             preserve_font_faces: Some(concrete_options.preserve_font_faces),
+            preserve_font_feature_values: Some(concrete_options.preserve_font_feature_values),
             preserve_important: Some(concrete_options.preserve_important),
+            preserve_keyframes: Some(concrete_options.preserve_keyframes),
             preserve_media_queries: Some(concrete_options.preserve_media_queries),
+            preserve_pages: Some(concrete_options.preserve_pages),
+            preserve_pseudo_classes: Some(concrete_options.preserve_pseudo_classes),
+            preserve_supports: Some(concrete_options.preserve_supports),
+            prune_media_queries: Some(concrete_options.prune_media_queries),
+            remove_link_tags: Some(concrete_options.remove_link_tags),
             remove_style_tags: Some(concrete_options.remove_style_tags),
+            sort_declarations: Some(concrete_options.sort_declarations),
         }
     }
 }
@@ -77,15 +255,31 @@ impl From<ConcreteOptions> for AbstractOptions {
 /// The required options to inline HTML and CSS. Use the other `Options`, it is has nice defaults.
 #[derive(Clone, Debug)]
 pub struct ConcreteOptions {
+    pub absolutize_urls: bool,
     pub apply_table_element_attributes: bool,
     pub apply_height_attributes: bool,
+    pub apply_link_tags: bool,
+    pub apply_pseudo_elements: bool,
     pub apply_style_tags: bool,
     pub apply_width_attributes: bool,
+    pub enforce_size_budget: bool,
+    pub expand_shorthands: bool,
+    pub inline_style_precedence: bool,
     pub insert_preserved_css: Vec<String>,
+    pub minify: bool,
+    pub prepend_preserved_css: bool,
     pub preserve_font_faces: bool,
+    pub preserve_font_feature_values: bool,
     pub preserve_important: bool,
+    pub preserve_keyframes: bool,
     pub preserve_media_queries: bool,
+    pub preserve_pages: bool,
+    pub preserve_pseudo_classes: bool,
+    pub preserve_supports: bool,
+    pub prune_media_queries: bool,
+    pub remove_link_tags: bool,
     pub remove_style_tags: bool,
+    pub sort_declarations: bool,
 }
 
 impl Default for ConcreteOptions {
@@ -97,22 +291,40 @@ impl Default for ConcreteOptions {
 impl From<AbstractOptions> for ConcreteOptions {
     fn from(abstract_options: AbstractOptions) -> Self {
         Self {
+            absolutize_urls: abstract_options.absolutize_urls.unwrap_or(false),
             apply_table_element_attributes: abstract_options
                 .apply_table_element_attributes
                 .unwrap_or(true),
             apply_height_attributes: abstract_options.apply_height_attributes.unwrap_or(true),
+            apply_link_tags: abstract_options.apply_link_tags.unwrap_or(true),
+            apply_pseudo_elements: abstract_options.apply_pseudo_elements.unwrap_or(false),
             apply_style_tags: abstract_options.apply_style_tags.unwrap_or(true),
             apply_width_attributes: abstract_options.apply_width_attributes.unwrap_or(true),
+            enforce_size_budget: abstract_options.enforce_size_budget.unwrap_or(false),
+            expand_shorthands: abstract_options.expand_shorthands.unwrap_or(false),
+            inline_style_precedence: abstract_options.inline_style_precedence.unwrap_or(true),
             insert_preserved_css: abstract_options.insert_preserved_css.unwrap_or_else(|| {
                 vec!["head", "body", "html"]
                     .iter()
                     .map(|x| x.to_string())
                     .collect()
             }),
+            minify: abstract_options.minify.unwrap_or(false),
+            prepend_preserved_css: abstract_options.prepend_preserved_css.unwrap_or(false),
             preserve_font_faces: abstract_options.preserve_font_faces.unwrap_or(true),
+            preserve_font_feature_values: abstract_options
+                .preserve_font_feature_values
+                .unwrap_or(true),
             preserve_important: abstract_options.preserve_important.unwrap_or(false),
+            preserve_keyframes: abstract_options.preserve_keyframes.unwrap_or(true),
             preserve_media_queries: abstract_options.preserve_media_queries.unwrap_or(true),
+            preserve_pages: abstract_options.preserve_pages.unwrap_or(true),
+            preserve_pseudo_classes: abstract_options.preserve_pseudo_classes.unwrap_or(true),
+            preserve_supports: abstract_options.preserve_supports.unwrap_or(true),
+            prune_media_queries: abstract_options.prune_media_queries.unwrap_or(false),
+            remove_link_tags: abstract_options.remove_link_tags.unwrap_or(true),
             remove_style_tags: abstract_options.remove_style_tags.unwrap_or(true),
+            sort_declarations: abstract_options.sort_declarations.unwrap_or(false),
         }
     }
 }
@@ -0,0 +1,95 @@
+use servo_css_parser::cssparser::{ParseError, Parser, ParserInput, Token};
+
+/// A pseudo-element that can be turned into a real element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PseudoElement {
+    Before,
+    After,
+}
+
+/// Splits a selector that targets a `::before` or `::after` pseudo-element into the selector of
+/// the element that it belongs to and the pseudo-element.
+pub fn split_pseudo_element(selector: &str) -> Option<(String, PseudoElement)> {
+    for (suffix, pseudo_element) in &[
+        ("::before", PseudoElement::Before),
+        ("::after", PseudoElement::After),
+        (":before", PseudoElement::Before),
+        (":after", PseudoElement::After),
+    ] {
+        if selector.len() < suffix.len()
+            || !selector.is_char_boundary(selector.len() - suffix.len())
+            || !selector[selector.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+        {
+            continue;
+        }
+
+        let selector = &selector[..selector.len() - suffix.len()];
+
+        // A pseudo-element on its own, such as `p > ::before`, belongs to any element.
+        if selector.is_empty()
+            || selector.ends_with(|c: char| c.is_whitespace() || c == '>' || c == '+' || c == '~')
+        {
+            return Some((format!("{}*", selector), *pseudo_element));
+        }
+
+        return Some((selector.to_string(), *pseudo_element));
+    }
+
+    None
+}
+
+/// A part of the value of the `content` property.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContentItem {
+    /// A string, used as is.
+    Text(String),
+
+    /// An `attr()` function, replaced by the value of the named attribute of the element.
+    Attribute(String),
+}
+
+/// Parses the value of the `content` property.
+///
+/// Returns `Some(None)` for `none` and `normal`, which don't generate a pseudo-element, and `None`
+/// for values that can't be represented as text, such as images and counters.
+pub fn parse_content(value: &str) -> Option<Option<Vec<ContentItem>>> {
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    let mut content = vec![];
+
+    loop {
+        let token = match parser.next() {
+            Ok(t) => t.clone(),
+            Err(_) => break,
+        };
+
+        match token {
+            Token::Ident(ref ident)
+                if content.is_empty()
+                    && (ident.eq_ignore_ascii_case("none")
+                        || ident.eq_ignore_ascii_case("normal")) =>
+            {
+                return match parser.expect_exhausted() {
+                    Ok(_) => Some(None),
+                    Err(_) => None,
+                };
+            }
+            Token::QuotedString(ref string) => content.push(ContentItem::Text(string.to_string())),
+            Token::Function(ref name) if name.eq_ignore_ascii_case("attr") => {
+                let attribute = parser.parse_nested_block(|p| {
+                    let attribute = p.expect_ident()?.to_string();
+                    p.expect_exhausted()?;
+                    Ok::<_, ParseError<()>>(attribute)
+                });
+
+                match attribute {
+                    Ok(a) => content.push(ContentItem::Attribute(a)),
+                    Err(_) => return None,
+                };
+            }
+            _ => return None,
+        };
+    }
+
+    Some(Some(content))
+}
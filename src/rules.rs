@@ -1,24 +1,118 @@
+use super::{
+    diagnostic::{CssSource, Location},
+    source_map::SourceMap,
+};
 use servo_css_parser::style::properties::declaration_block::PropertyDeclarationBlock;
+use std::sync::Arc;
+
+/// A single CSS selector and the declarations it applies.
+#[derive(Clone, Debug)]
+pub struct StyleRule {
+    /// The selector, serialized so it can be matched against the HTML document.
+    pub selector: String,
+
+    /// The specificity of the selector, used to order rules in the cascade.
+    pub specificity: u32,
+
+    /// The declarations of the rule.
+    pub block: PropertyDeclarationBlock,
+
+    /// Where the rule is in `source`.
+    pub location: Location,
+
+    /// The CSS that the rule came from.
+    pub source: CssSource,
+}
+
+/// The kind of CSS rule that was preserved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreservedRuleKind {
+    /// An `@font-face` rule.
+    FontFace,
+
+    /// An `@font-feature-values` rule.
+    FontFeatureValues,
+
+    /// An `@import` rule for a stylesheet that couldn't be loaded, such as one on another host.
+    Import,
+
+    /// An `@keyframes` rule.
+    Keyframes,
+
+    /// An `@media` rule.
+    Media,
+
+    /// An `@page` rule.
+    Page,
+
+    /// A style rule with stateful pseudo-classes, such as `a:hover`, that can't be inlined.
+    PseudoClass,
+
+    /// An `@supports` rule.
+    Supports,
+}
+
+/// A rule inside a preserved `@media` rule.
+#[derive(Clone, Debug)]
+pub struct NestedRule {
+    /// The selectors of a style rule, used to find rules that don't match the HTML document.
+    /// Empty for any other kind of rule.
+    pub selectors: Vec<String>,
+
+    /// The CSS of the rule.
+    pub css: String,
+}
+
+/// The parts of a preserved `@media` rule.
+#[derive(Clone, Debug)]
+pub struct MediaBlock {
+    /// The media query list.
+    pub query: String,
+
+    /// The rules inside the `@media` rule, in source order.
+    pub rules: Vec<NestedRule>,
+}
+
+/// A CSS rule that can't be inlined, kept as CSS to be inserted into the HTML document.
+#[derive(Clone, Debug)]
+pub struct PreservedRule {
+    /// The kind of CSS rule.
+    pub kind: PreservedRuleKind,
+
+    /// The CSS of the rule.
+    pub css: String,
+
+    /// The parts of an `@media` rule, so that it can be pruned. `None` for any other kind of rule.
+    pub media: Option<MediaBlock>,
+}
 
 /// Data collected from the CSS stylesheet.
 #[derive(Clone, Debug)]
 pub struct Rules {
-    /// Style rules.
-    pub style: Vec<(String, PropertyDeclarationBlock)>,
+    /// Style rules, in source order. They are shared with every HTML document inlined with the
+    /// same compiled stylesheet, and only copied for an HTML document with style rules of its own.
+    pub style: Arc<Vec<StyleRule>>,
+
+    /// Preserved rules, in source order, so that they cascade the same way once inserted.
+    pub preserved: Vec<PreservedRule>,
 
-    /// `@media` rules.
-    pub media: Vec<String>,
+    /// Rules that servo can't parse, such as `@page` rules, and where they are in the CSS
+    /// stylesheet. They are moved into `preserved`, in source order, when the other rules are
+    /// collected.
+    pub unparsed: Vec<(Location, PreservedRule)>,
 
-    /// `@font-face` rules.
-    pub font_face: Vec<String>,
+    /// Where each part of the CSS stylesheet came from, so that locations in it can be reported
+    /// relative to the CSS they are in.
+    pub source_map: SourceMap,
 }
 
 impl Default for Rules {
     fn default() -> Self {
         Self {
-            style: vec![],
-            media: vec![],
-            font_face: vec![],
+            style: Arc::new(vec![]),
+            preserved: vec![],
+            unparsed: vec![],
+            source_map: SourceMap::default(),
         }
     }
 }
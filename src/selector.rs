@@ -0,0 +1,303 @@
+use servo_css_parser::cssparser::{ParseError, Parser, ParserInput, Token};
+use std::ops::AddAssign;
+
+/// Functional pseudo-classes that match an element when any selector in their list does.
+const MATCHES_ANY_PSEUDO_CLASSES: &[&str] = &["is", "where", "matches", "-webkit-any"];
+
+/// Pseudo-elements that can be written with a single colon, like a pseudo-class.
+const LEGACY_PSEUDO_ELEMENTS: &[&str] = &["before", "after", "first-line", "first-letter"];
+
+/// The largest count of each kind of selector that fits in a packed specificity.
+const MAX_10BIT: u32 = (1 << 10) - 1;
+
+/// The specificity of a selector, as counts of each kind of simple selector in it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Specificity {
+    /// ID selectors.
+    ids: u32,
+
+    /// Class selectors, attribute selectors, and pseudo-classes.
+    classes: u32,
+
+    /// Type selectors and pseudo-elements.
+    elements: u32,
+}
+
+impl AddAssign for Specificity {
+    fn add_assign(self: &mut Self, other: Self) {
+        self.ids += other.ids;
+        self.classes += other.classes;
+        self.elements += other.elements;
+    }
+}
+
+impl From<Specificity> for u32 {
+    /// Packs a specificity into a `u32` the same way servo does, so they can be compared.
+    fn from(specificity: Specificity) -> Self {
+        (specificity.ids.min(MAX_10BIT) << 20)
+            | (specificity.classes.min(MAX_10BIT) << 10)
+            | specificity.elements.min(MAX_10BIT)
+    }
+}
+
+/// Consumes the block of the function or bracket token that `parser` just returned, and returns
+/// the CSS inside of it.
+fn consume_block<'a>(parser: &mut Parser, css: &'a str) -> &'a str {
+    let start = parser.position().byte_index();
+    let _ = parser.parse_nested_block(|p| {
+        while p.next_including_whitespace().is_ok() {}
+        Ok::<_, ParseError<()>>(())
+    });
+    let block = &css[start..parser.position().byte_index()];
+
+    block.strip_suffix(')').unwrap_or(block)
+}
+
+/// Returns the next token, and the CSS inside of it when it is a function or a bracket token. The
+/// block is always consumed, so the position of the parser is after the whole token.
+fn next_token<'i, 'a>(parser: &mut Parser<'i, '_>, css: &'a str) -> Option<(Token<'i>, &'a str)> {
+    let token = parser.next_including_whitespace().ok()?.clone();
+    let block = match token {
+        Token::Function(_)
+        | Token::ParenthesisBlock
+        | Token::SquareBracketBlock
+        | Token::CurlyBracketBlock => consume_block(parser, css),
+        _ => "",
+    };
+
+    Some((token, block))
+}
+
+/// Splits a selector list at the commas that aren't nested in functional pseudo-classes.
+fn split_selector_list(selectors: &str) -> Vec<&str> {
+    let mut input = ParserInput::new(selectors);
+    let mut parser = Parser::new(&mut input);
+    let mut list = vec![];
+    let mut start = 0;
+
+    loop {
+        let position = parser.position().byte_index();
+        match next_token(&mut parser, selectors) {
+            Some((Token::Comma, _)) => {
+                list.push(selectors[start..position].trim());
+                start = parser.position().byte_index();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    list.push(selectors[start..].trim());
+    list.retain(|selector| !selector.is_empty());
+
+    list
+}
+
+/// Returns where the last compound selector of a complex selector starts, after its last
+/// combinator.
+fn last_compound_start(selector: &str) -> usize {
+    let mut input = ParserInput::new(selector);
+    let mut parser = Parser::new(&mut input);
+    let mut start = 0;
+
+    while let Some((token, _)) = next_token(&mut parser, selector) {
+        match token {
+            Token::WhiteSpace(_) | Token::Delim('>') | Token::Delim('+') | Token::Delim('~') => {
+                start = parser.position().byte_index()
+            }
+            _ => {}
+        }
+    }
+
+    start
+}
+
+/// Splits a compound selector into its type selector, if it starts with one, and the rest.
+fn split_type_selector(compound: &str) -> (&str, &str) {
+    let mut input = ParserInput::new(compound);
+    let mut parser = Parser::new(&mut input);
+
+    match next_token(&mut parser, compound) {
+        Some((Token::Ident(_), _)) | Some((Token::Delim('*'), _)) => {
+            compound.split_at(parser.position().byte_index())
+        }
+        _ => ("", compound),
+    }
+}
+
+/// Combines two compound selectors into one that matches the elements both of them match, or
+/// returns `None` when their type selectors can never match the same element.
+fn merge_compounds(compound: &str, other: &str) -> Option<String> {
+    let (type_selector, rest) = split_type_selector(compound);
+    let (other_type_selector, other_rest) = split_type_selector(other);
+
+    let type_selector = match (type_selector, other_type_selector) {
+        ("", t) | (t, "") | ("*", t) | (t, "*") => t,
+        (t, u) if t.eq_ignore_ascii_case(u) => t,
+        _ => return None,
+    };
+
+    Some(format!("{}{}{}", type_selector, rest, other_rest))
+}
+
+/// Appends a selector from the list of a functional pseudo-class, such as `:is()`, to the complex
+/// selector before it. The last compound selectors of both are merged, and any compound selectors
+/// before the last one in the argument are placed after the ones in the selector, so an argument
+/// with combinators matches only the elements it would match inside the selector.
+fn append_argument(selector: &str, argument: &str) -> Option<String> {
+    let compound_start = last_compound_start(selector);
+    let argument_compound_start = last_compound_start(argument);
+    let compound = merge_compounds(
+        &selector[compound_start..],
+        &argument[argument_compound_start..],
+    )?;
+
+    Some(format!(
+        "{}{}{}",
+        &selector[..compound_start],
+        &argument[..argument_compound_start],
+        compound
+    ))
+}
+
+/// Expands a complex selector, without commas, the same way as `expand_selector`.
+fn expand_complex_selector(selector: &str) -> Vec<String> {
+    let mut input = ParserInput::new(selector);
+    let mut parser = Parser::new(&mut input);
+    let mut expanded_selectors = vec![String::default()];
+    let mut start = 0;
+    let mut colon_start = 0;
+    let mut colons = 0;
+
+    loop {
+        let position = parser.position().byte_index();
+        let (token, block) = match next_token(&mut parser, selector) {
+            Some(t) => t,
+            None => break,
+        };
+
+        match token {
+            Token::Function(ref name) if colons == 1 => {
+                let name = name.to_ascii_lowercase();
+                let is_matches_any = MATCHES_ANY_PSEUDO_CLASSES.contains(&name.as_str());
+                if is_matches_any || name == "not" {
+                    let arguments = expand_selector(block);
+                    let before = &selector[start..colon_start];
+                    start = parser.position().byte_index();
+
+                    expanded_selectors = if is_matches_any {
+                        expanded_selectors
+                            .iter()
+                            .flat_map(|expanded_selector| {
+                                let expanded_selector = format!("{}{}", expanded_selector, before);
+                                arguments
+                                    .iter()
+                                    .filter_map(|argument| {
+                                        append_argument(&expanded_selector, argument)
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                            .collect()
+                    } else {
+                        // An element matches `:not()` with a list when it matches none of the
+                        // selectors, and kuchiki only supports one selector in `:not()`.
+                        let negations = arguments
+                            .iter()
+                            .map(|argument| format!(":not({})", argument))
+                            .collect::<String>();
+                        expanded_selectors
+                            .iter()
+                            .map(|expanded_selector| {
+                                format!("{}{}{}", expanded_selector, before, negations)
+                            })
+                            .collect()
+                    };
+                }
+                colons = 0;
+            }
+            Token::Colon => {
+                if colons == 0 {
+                    colon_start = position;
+                }
+                colons += 1;
+            }
+            _ => colons = 0,
+        };
+    }
+
+    expanded_selectors
+        .into_iter()
+        .map(|expanded_selector| expanded_selector + &selector[start..])
+        .collect()
+}
+
+/// Expands a selector list into a list of selectors that kuchiki can match. kuchiki can't match
+/// `:is()` and `:where()`, so each selector in their lists is inlined into its own selector, and
+/// it only supports one selector in `:not()`, so a `:not()` list is split into one `:not()` for
+/// each selector. Selectors that can never match an element, such as `p:is(div)`, are dropped.
+pub fn expand_selector(selector: &str) -> Vec<String> {
+    split_selector_list(selector)
+        .into_iter()
+        .flat_map(expand_complex_selector)
+        .collect()
+}
+
+/// Counts the simple selectors in a complex selector.
+fn count_specificity(selector: &str) -> Specificity {
+    let mut input = ParserInput::new(selector);
+    let mut parser = Parser::new(&mut input);
+    let mut specificity = Specificity::default();
+    let mut colons = 0;
+    let mut is_name = false;
+
+    while let Some((token, block)) = next_token(&mut parser, selector) {
+        // A single colon starts a pseudo-class, and two start a pseudo-element.
+        match token {
+            Token::Colon => {
+                colons += 1;
+                continue;
+            }
+            Token::IDHash(_) => specificity.ids += 1,
+            Token::Delim('.') | Token::SquareBracketBlock => specificity.classes += 1,
+            Token::Ident(ref name)
+                if colons == 1
+                    && !LEGACY_PSEUDO_ELEMENTS
+                        .iter()
+                        .any(|pseudo_element| name.eq_ignore_ascii_case(pseudo_element)) =>
+            {
+                specificity.classes += 1
+            }
+            Token::Ident(_) if colons > 0 || !is_name => specificity.elements += 1,
+            Token::Function(ref name) if colons == 1 => {
+                let name = name.to_ascii_lowercase();
+
+                // `:where()` has no specificity, while `:is()` and `:not()` have the specificity
+                // of the most specific selector in their list.
+                if name == "not"
+                    || (name != "where" && MATCHES_ANY_PSEUDO_CLASSES.contains(&name.as_str()))
+                {
+                    specificity += split_selector_list(block)
+                        .into_iter()
+                        .map(count_specificity)
+                        .max()
+                        .unwrap_or_default();
+                } else if name != "where" {
+                    specificity.classes += 1;
+                }
+            }
+            Token::Function(_) if colons > 1 => specificity.elements += 1,
+            _ => {}
+        };
+
+        // The name of a class or of a namespaced type selector isn't a type selector itself.
+        is_name = token == Token::Delim('.') || token == Token::Delim('|');
+        colons = 0;
+    }
+
+    specificity
+}
+
+/// Computes the specificity of a selector, packed the same way as the specificity servo computes,
+/// including the specificity of `:is()`, `:where()`, and `:not()` with a selector list.
+pub fn selector_specificity(selector: &str) -> u32 {
+    count_specificity(selector).into()
+}
@@ -1,8 +1,13 @@
+use super::loader::StylesheetLoader;
 use maplit::hashmap;
-use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
 
 /// Settings referenced by features enabled through `Options`.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct AbstractSettings {
     /// List of HTML elements that can receive `width` attributes.
     ///
@@ -32,7 +37,9 @@ pub struct AbstractSettings {
     ///
     pub height_elements: Option<Vec<String>>,
 
-    /// Map of style property names to their respective attribute names.
+    /// Map of style property names to their respective attribute names. Longhands are mapped
+    /// even when they are set by a shorthand, and shorthands are mapped when all of their
+    /// longhands are set.
     ///
     /// Defaults to:
     ///
@@ -102,6 +109,80 @@ pub struct AbstractSettings {
     /// ```
     ///
     pub excluded_properties: Option<Vec<String>>,
+
+    /// List of pseudo-classes that depend on how the user interacts with the document, so style
+    /// rules that use them can't be inlined.
+    ///
+    /// Defaults to:
+    ///
+    /// ```
+    /// vec![
+    ///     "hover",
+    ///     "active",
+    ///     "focus",
+    ///     "focus-within",
+    ///     "focus-visible",
+    ///     "visited",
+    ///     "target",
+    /// ];
+    /// ```
+    ///
+    pub stateful_pseudo_classes: Option<Vec<String>>,
+
+    /// Font size, in pixels, used to convert `em` and `rem` lengths to pixels for `width` and
+    /// `height` attributes. `pt` lengths are always converted at `1pt = 4/3px`.
+    ///
+    /// Defaults to:
+    ///
+    /// ```
+    /// 16.0;
+    /// ```
+    ///
+    pub root_font_size: Option<f32>,
+
+    /// Size, in bytes, that the inlined HTML document should stay under. Gmail clips messages
+    /// larger than about 102KB, which hides anything after the clip, such as unsubscribe links.
+    /// Going over the budget adds a warning to `diagnostics`, or returns an error from the `try_*`
+    /// functions when `Options.enforce_size_budget` is `true`. Preserved CSS rules returned
+    /// separately by the `*_result` functions count towards the budget.
+    ///
+    /// Defaults to:
+    ///
+    /// ```
+    /// 102 * 1024;
+    /// ```
+    ///
+    pub size_budget: Option<usize>,
+
+    /// Name of the element that the HTML is parsed as the contents of, such as `body` or `td`, to
+    /// inline a fragment of HTML instead of a whole document. Only the fragment is serialized,
+    /// without `<html>`, `<head>`, and `<body>` tags, and preserved CSS rules are only inserted
+    /// into a placeholder in it: use `Eyeliner::preserved_css` to get them instead.
+    ///
+    /// Defaults to `None`, which parses the HTML as a whole document.
+    ///
+    pub fragment_context_element: Option<String>,
+
+    /// URL that relative URLs are resolved against, such as `https://example.com/emails/`. It is
+    /// used as the URL of the CSS stylesheet, and by `Options.absolutize_urls`. An invalid URL is
+    /// ignored with a warning in `diagnostics`, or is an error for the `try_` functions.
+    ///
+    /// Defaults to `None`, which leaves relative URLs as they are.
+    ///
+    pub base_url: Option<String>,
+
+    /// Loader used to get the CSS of stylesheets referenced by `<link rel="stylesheet" />` tags and
+    /// `@import` rules, such as `FileSystemLoader` or `MemoryLoader`. `@import` rules and tags for
+    /// stylesheets on another host that it can't load are preserved as they are.
+    ///
+    /// Defaults to `None`, which leaves `<link rel="stylesheet" />` tags as they are, and
+    /// preserves `@import` rules.
+    ///
+    /// Loaders can't be read from or written to config files, so this is always `None` after
+    /// deserializing.
+    ///
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub stylesheet_loader: Option<Arc<dyn StylesheetLoader>>,
 }
 
 impl Default for AbstractSettings {
@@ -113,6 +194,37 @@ impl Default for AbstractSettings {
             table_elements: None,
             non_visual_elements: None,
             excluded_properties: None,
+            stateful_pseudo_classes: None,
+            root_font_size: None,
+            size_budget: None,
+            fragment_context_element: None,
+            base_url: None,
+            stylesheet_loader: None,
+        }
+    }
+}
+
+impl AbstractSettings {
+    /// Returns these settings with every field that is set in `overrides` replaced by the
+    /// override.
+    pub fn merge(self, overrides: Self) -> Self {
+        Self {
+            width_elements: overrides.width_elements.or(self.width_elements),
+            height_elements: overrides.height_elements.or(self.height_elements),
+            style_to_attribute: overrides.style_to_attribute.or(self.style_to_attribute),
+            table_elements: overrides.table_elements.or(self.table_elements),
+            non_visual_elements: overrides.non_visual_elements.or(self.non_visual_elements),
+            excluded_properties: overrides.excluded_properties.or(self.excluded_properties),
+            stateful_pseudo_classes: overrides
+                .stateful_pseudo_classes
+                .or(self.stateful_pseudo_classes),
+            root_font_size: overrides.root_font_size.or(self.root_font_size),
+            size_budget: overrides.size_budget.or(self.size_budget),
+            fragment_context_element: overrides
+                .fragment_context_element
+                .or(self.fragment_context_element),
+            base_url: overrides.base_url.or(self.base_url),
+            stylesheet_loader: overrides.stylesheet_loader.or(self.stylesheet_loader),
         }
     }
 }
@@ -126,6 +238,12 @@ impl From<ConcreteSettings> for AbstractSettings {
             table_elements: Some(concrete_settings.table_elements),
             non_visual_elements: Some(concrete_settings.non_visual_elements),
             excluded_properties: Some(concrete_settings.excluded_properties),
+            stateful_pseudo_classes: Some(concrete_settings.stateful_pseudo_classes),
+            root_font_size: Some(concrete_settings.root_font_size),
+            size_budget: Some(concrete_settings.size_budget),
+            fragment_context_element: concrete_settings.fragment_context_element,
+            base_url: concrete_settings.base_url,
+            stylesheet_loader: concrete_settings.stylesheet_loader,
         }
     }
 }
@@ -140,6 +258,12 @@ pub struct ConcreteSettings {
     pub table_elements: Vec<String>,
     pub non_visual_elements: Vec<String>,
     pub excluded_properties: Vec<String>,
+    pub stateful_pseudo_classes: Vec<String>,
+    pub root_font_size: f32,
+    pub size_budget: usize,
+    pub fragment_context_element: Option<String>,
+    pub base_url: Option<String>,
+    pub stylesheet_loader: Option<Arc<dyn StylesheetLoader>>,
 }
 
 impl From<AbstractSettings> for ConcreteSettings {
@@ -189,6 +313,27 @@ impl From<AbstractSettings> for ConcreteSettings {
             excluded_properties: abstract_settings
                 .excluded_properties
                 .unwrap_or_else(|| vec![]),
+            stateful_pseudo_classes: abstract_settings.stateful_pseudo_classes.unwrap_or_else(
+                || {
+                    [
+                        "hover",
+                        "active",
+                        "focus",
+                        "focus-within",
+                        "focus-visible",
+                        "visited",
+                        "target",
+                    ]
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect()
+                },
+            ),
+            root_font_size: abstract_settings.root_font_size.unwrap_or(16.0),
+            size_budget: abstract_settings.size_budget.unwrap_or(102 * 1024),
+            fragment_context_element: abstract_settings.fragment_context_element,
+            base_url: abstract_settings.base_url,
+            stylesheet_loader: abstract_settings.stylesheet_loader,
         }
     }
 }
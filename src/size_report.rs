@@ -0,0 +1,49 @@
+use kuchiki::NodeRef;
+
+/// The serialized size of an HTML document, in bytes, broken down by what contributes to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SizeReport {
+    /// The size of the whole HTML document.
+    pub total: usize,
+
+    /// The size of the values of `style` attributes.
+    pub inline_styles: usize,
+
+    /// The size of the CSS in `<style />` tags, such as preserved CSS rules.
+    pub preserved_css: usize,
+
+    /// The size of everything else, such as elements, text, and other attributes.
+    pub markup: usize,
+}
+
+impl SizeReport {
+    /// Measures an HTML document, given the HTML that it is serialized to.
+    pub fn new(document: &NodeRef, html: &str) -> Self {
+        let total = html.len();
+
+        let inline_styles = match document.select("[style]") {
+            Ok(elements) => elements
+                .map(|element| {
+                    element
+                        .attributes
+                        .borrow()
+                        .get("style")
+                        .map_or(0, |style| style.len())
+                })
+                .sum(),
+            Err(_) => 0,
+        };
+
+        let preserved_css = match document.select("style") {
+            Ok(elements) => elements.map(|element| element.text_contents().len()).sum(),
+            Err(_) => 0,
+        };
+
+        Self {
+            total,
+            inline_styles,
+            preserved_css,
+            markup: total.saturating_sub(inline_styles + preserved_css),
+        }
+    }
+}
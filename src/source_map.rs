@@ -0,0 +1,107 @@
+use super::diagnostic::{CssSource, Location};
+
+/// The location of the start of CSS.
+const START: Location = Location { line: 1, column: 1 };
+
+/// Returns where `css` ends, when it starts at `location`. Lines are counted the same way as the
+/// CSS parser does, and columns in UTF-16 code units.
+fn advance(location: Location, css: &str) -> Location {
+    let mut location = location;
+    let mut chars = css.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' | '\n' | '\x0C' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                location.line += 1;
+                location.column = 1;
+            }
+            _ => location.column += c.len_utf16() as u32,
+        };
+    }
+
+    location
+}
+
+/// Where each part of CSS that is joined from several sources came from, so that locations in
+/// the joined CSS can be reported relative to the source they are in.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    /// Where each part starts in the joined CSS, its source, and where it starts in the source,
+    /// in order.
+    spans: Vec<(Location, CssSource, Location)>,
+}
+
+impl SourceMap {
+    /// Returns the source that a location in the joined CSS is in, and where it is in the source.
+    pub fn resolve(self: &Self, location: Location) -> (CssSource, Location) {
+        let span = self
+            .spans
+            .iter()
+            .rev()
+            .find(|(start, ..)| *start <= location);
+        let (start, source, source_start) = match span {
+            Some(s) => s,
+            None => return (CssSource::Css, location),
+        };
+
+        let source_location = if location.line == start.line {
+            Location {
+                line: source_start.line,
+                column: source_start.column + location.column - start.column,
+            }
+        } else {
+            Location {
+                line: source_start.line + location.line - start.line,
+                column: location.column,
+            }
+        };
+
+        (source.clone(), source_location)
+    }
+}
+
+/// CSS joined from several sources, such as the `css` argument and `<style />` tags, along with
+/// where each part of it came from.
+#[derive(Clone, Debug)]
+pub(crate) struct JoinedCss {
+    /// The joined CSS.
+    pub css: String,
+
+    /// Where each part of `css` came from.
+    pub source_map: SourceMap,
+
+    /// Where `css` ends.
+    end: Location,
+}
+
+impl Default for JoinedCss {
+    fn default() -> Self {
+        Self {
+            css: String::new(),
+            source_map: SourceMap::default(),
+            end: START,
+        }
+    }
+}
+
+impl JoinedCss {
+    /// Appends CSS from `source`, which starts at the `byte_index` of `source_css`.
+    pub fn push(self: &mut Self, source_css: &str, byte_index: usize, source: &CssSource) {
+        let source_start = advance(START, &source_css[..byte_index]);
+        self.source_map
+            .spans
+            .push((self.end, source.clone(), source_start));
+        self.push_generated(&source_css[byte_index..]);
+    }
+
+    /// Appends CSS that doesn't come from a source, such as the `@media` rule that a stylesheet
+    /// imported with a media query list is wrapped in. It is reported as part of the CSS before
+    /// it.
+    pub fn push_generated(self: &mut Self, css: &str) {
+        self.end = advance(self.end, css);
+        self.css.push_str(css);
+    }
+}
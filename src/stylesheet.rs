@@ -0,0 +1,684 @@
+use super::{
+    diagnostic::{CssSource, Diagnostic, Location, Severity},
+    error::Error,
+    imports::resolve_imports,
+    options::ConcreteOptions,
+    rules::{MediaBlock, NestedRule, PreservedRule, PreservedRuleKind, Rules, StyleRule},
+    selector::{expand_selector, selector_specificity},
+    settings::ConcreteSettings,
+    source_map::{JoinedCss, SourceMap},
+};
+use kuchiki::Selectors;
+use servo_css_parser::{
+    cssparser::{Delimiter, ParseError, Parser, ParserInput, SourceLocation, Token},
+    style::{
+        error_reporting::{ContextualParseError, ParseErrorReporter},
+        properties::{declaration_block::PropertyDeclarationBlock, PropertyId},
+        servo_arc::Arc,
+        shared_lock::{SharedRwLock, SharedRwLockReadGuard},
+        stylesheets::{CssRule, StyleRule as CssStyleRule, UrlExtraData},
+    },
+    types::{MediaList, Origin, QuirksMode, ServoStylesheet as Stylesheet, Url},
+};
+use std::cell::RefCell;
+
+trait RemoveExcludedPropertiesFromPropertyDeclarationBlock {
+    fn remove_excluded_properties(self: &mut Self, properties: &[String]) -> &mut Self;
+}
+impl RemoveExcludedPropertiesFromPropertyDeclarationBlock for PropertyDeclarationBlock {
+    fn remove_excluded_properties(self: &mut Self, properties: &[String]) -> &mut Self {
+        for property_id in properties {
+            if let Ok(ref id) = PropertyId::parse_enabled_for_all_content(property_id) {
+                if let Some(first_declaration_to_remove) = self.first_declaration_to_remove(id) {
+                    self.remove_property(id, first_declaration_to_remove);
+                }
+            }
+        }
+
+        self
+    }
+}
+
+/// Whether servo reports a declaration of a CSS property that it doesn't know, such as
+/// `mso-line-height-rule` or `-ms-interpolation-mode`, which is valid CSS that email clients may
+/// support, rather than CSS that can't be parsed.
+fn is_unknown_property(error: &ContextualParseError) -> bool {
+    let declaration = match *error {
+        ContextualParseError::UnsupportedPropertyDeclaration(declaration, ..)
+        | ContextualParseError::UnsupportedKeyframePropertyDeclaration(declaration, ..) => {
+            declaration
+        }
+        _ => return false,
+    };
+
+    let mut input = ParserInput::new(declaration);
+    let mut parser = Parser::new(&mut input);
+    match parser.expect_ident_cloned() {
+        Ok(name) => {
+            parser.expect_colon().is_ok()
+                && PropertyId::parse_enabled_for_all_content(&name).is_err()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Collects errors and warnings reported while parsing the CSS stylesheet. Unknown properties are
+/// ignored with a warning, and anything else is an error. Locations are reported relative to the
+/// source they are in.
+struct CollectErrorReporter<'a> {
+    source_map: &'a SourceMap,
+    errors: RefCell<Vec<Error>>,
+    diagnostics: RefCell<Vec<Diagnostic>>,
+}
+impl<'a> ParseErrorReporter for CollectErrorReporter<'a> {
+    fn report_error(
+        self: &Self,
+        _url: &UrlExtraData,
+        location: SourceLocation,
+        error: ContextualParseError,
+    ) {
+        let (source, location) = self.source_map.resolve(Location::from(location));
+        if is_unknown_property(&error) {
+            self.diagnostics.borrow_mut().push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!("Ignored unknown CSS property. {}", error),
+                location: Some(location),
+                source: Some(source),
+            });
+            return;
+        }
+
+        let error = Error::Css {
+            message: error.to_string(),
+            source,
+            line: location.line,
+            column: location.column,
+        };
+        self.diagnostics
+            .borrow_mut()
+            .push(Diagnostic::from(error.clone()));
+        self.errors.borrow_mut().push(error);
+    }
+}
+
+/// The URL the CSS stylesheet is parsed with when `Settings.base_url` isn't set.
+const STYLESHEET_URL: &str = "about::test";
+
+/// Returns the URL the CSS stylesheet is parsed with, which is `Settings.base_url` if it is set.
+pub(crate) fn stylesheet_url(settings: &ConcreteSettings) -> Result<Url, Error> {
+    Url::parse(settings.base_url.as_deref().unwrap_or(STYLESHEET_URL))
+        .map_err(|e| Error::InvalidUrl(e.to_string()))
+}
+
+/// Returns the URL the CSS stylesheet is parsed with, or `about:blank` and a warning if
+/// `Settings.base_url` is invalid, since relative URLs can't be resolved without it.
+pub(crate) fn lenient_stylesheet_url(settings: &ConcreteSettings) -> (Url, Option<Diagnostic>) {
+    match stylesheet_url(settings) {
+        Ok(url) => (url, None),
+        Err(error) => (
+            Url::parse("about:blank").expect("`about:blank` is a valid URL"),
+            Some(Diagnostic {
+                severity: Severity::Warning,
+                message: format!("Ignored `Settings.base_url`. {}", error),
+                location: None,
+                source: None,
+            }),
+        ),
+    }
+}
+
+/// Checks that selectors in options and CSS properties in settings are valid.
+pub(crate) fn validate(
+    options: &ConcreteOptions,
+    settings: &ConcreteSettings,
+) -> Result<(), Error> {
+    for selector in &options.insert_preserved_css {
+        if Selectors::compile(selector).is_err() {
+            return Err(Error::InvalidSelector(selector.clone()));
+        }
+    }
+
+    for property in settings
+        .excluded_properties
+        .iter()
+        .chain(settings.style_to_attribute.keys())
+    {
+        if PropertyId::parse_enabled_for_all_content(property).is_err() {
+            return Err(Error::UnknownProperty(property.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// At-rules that servo can't parse, so they are taken out of the CSS before it is parsed.
+const UNPARSED_AT_RULES: [(&str, PreservedRuleKind); 2] = [
+    ("font-feature-values", PreservedRuleKind::FontFeatureValues),
+    ("page", PreservedRuleKind::Page),
+];
+
+/// Splits the top-level at-rules in `UNPARSED_AT_RULES` from the rest of the CSS.
+///
+/// Returns the CSS with each of those rules replaced by whitespace, so that the other rules keep
+/// their locations, and each rule with where it was.
+fn split_unparsed_rules(css: &str) -> (String, Vec<(Location, PreservedRule)>) {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+    let mut remaining_css = String::with_capacity(css.len());
+    let mut unparsed_rules = vec![];
+    let mut end = 0;
+
+    loop {
+        parser.skip_whitespace();
+        let location = Location::from(parser.current_source_location());
+        let start = parser.position().byte_index();
+        let kind = match parser.next() {
+            Ok(Token::AtKeyword(ref name)) => UNPARSED_AT_RULES
+                .iter()
+                .find(|(at_rule_name, _)| name.eq_ignore_ascii_case(at_rule_name))
+                .map(|(_, kind)| *kind),
+            Ok(_) => None,
+            Err(_) => break,
+        };
+
+        let kind = match kind {
+            Some(k) => k,
+            None => continue,
+        };
+
+        let _ =
+            parser.parse_until_after(Delimiter::Semicolon | Delimiter::CurlyBracketBlock, |p| {
+                while p.next().is_ok() {}
+                Ok::<_, ParseError<()>>(())
+            });
+        let rule_end = parser.position().byte_index();
+
+        remaining_css += &css[end..start];
+        remaining_css.extend(
+            css[start..rule_end]
+                .chars()
+                .map(|c| if c == '\n' { c } else { ' ' }),
+        );
+        end = rule_end;
+
+        unparsed_rules.push((
+            location,
+            PreservedRule {
+                kind,
+                css: css[start..rule_end].trim().to_string(),
+                media: None,
+            },
+        ));
+    }
+    remaining_css += &css[end..];
+
+    (remaining_css, unparsed_rules)
+}
+
+/// Parses CSS into a stylesheet. At-rules that servo can't parse are returned separately, with
+/// where they are in the CSS.
+///
+/// Errors found in the CSS are recorded in `errors`, and every problem found, including unknown
+/// properties that are only warnings, is recorded in `diagnostics`, where in `source_map` it
+/// came from.
+pub(crate) fn parse_stylesheet(
+    css: &str,
+    url: Url,
+    source_map: &SourceMap,
+    errors: &mut Vec<Error>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (Stylesheet, Vec<(Location, PreservedRule)>) {
+    let (css, unparsed_rules) = split_unparsed_rules(css);
+    let error_reporter = CollectErrorReporter {
+        source_map,
+        errors: RefCell::new(vec![]),
+        diagnostics: RefCell::new(vec![]),
+    };
+    let shared_lock = SharedRwLock::new();
+    let media = Arc::new(shared_lock.wrap(MediaList::empty()));
+    let stylesheet = Stylesheet::from_str(
+        &css,
+        url,
+        Origin::UserAgent,
+        media,
+        shared_lock,
+        None,
+        Some(&error_reporter),
+        QuirksMode::NoQuirks,
+        0,
+    );
+
+    errors.extend(error_reporter.errors.into_inner());
+    diagnostics.extend(error_reporter.diagnostics.into_inner());
+
+    (stylesheet, unparsed_rules)
+}
+
+/// Whether a selector uses any of `pseudo_classes`, including inside functional pseudo-classes
+/// such as `:not()`.
+fn has_pseudo_class(parser: &mut Parser, pseudo_classes: &[String]) -> bool {
+    let mut colons = 0;
+    while let Ok(token) = parser.next_including_whitespace() {
+        let token = token.clone();
+
+        // A single colon starts a pseudo-class, and two start a pseudo-element.
+        match token {
+            Token::Ident(ref name) | Token::Function(ref name)
+                if colons == 1 && pseudo_classes.contains(&name.to_ascii_lowercase()) =>
+            {
+                return true;
+            }
+            Token::Colon => colons += 1,
+            _ => colons = 0,
+        };
+
+        let is_nested_match = match token {
+            Token::Function(_) | Token::ParenthesisBlock | Token::SquareBracketBlock => parser
+                .parse_nested_block(|p| {
+                    Ok::<_, ParseError<()>>(has_pseudo_class(p, pseudo_classes))
+                })
+                .unwrap_or(false),
+            _ => false,
+        };
+        if is_nested_match {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Whether a selector depends on how the user interacts with the document, such as `a:hover`, so
+/// it can't be inlined.
+fn is_stateful_selector(selector: &str, pseudo_classes: &[String]) -> bool {
+    let mut input = ParserInput::new(selector);
+    let mut parser = Parser::new(&mut input);
+    has_pseudo_class(&mut parser, pseudo_classes)
+}
+
+/// Removes `pseudo_classes` from a selector, so that it matches the elements it would match when
+/// the user interacts with the document. Functional pseudo-classes, such as `:not()`, are kept.
+pub(crate) fn strip_pseudo_classes(selector: &str, pseudo_classes: &[String]) -> String {
+    let mut input = ParserInput::new(selector);
+    let mut parser = Parser::new(&mut input);
+    let mut stripped_selector = String::default();
+    let mut start = 0;
+    let mut colon_start = 0;
+    let mut colons = 0;
+
+    loop {
+        let position = parser.position().byte_index();
+        let token = match parser.next_including_whitespace() {
+            Ok(t) => t.clone(),
+            Err(_) => break,
+        };
+
+        match token {
+            Token::Ident(ref name)
+                if colons == 1 && pseudo_classes.contains(&name.to_ascii_lowercase()) =>
+            {
+                stripped_selector.push_str(&selector[start..colon_start]);
+                start = parser.position().byte_index();
+                colons = 0;
+            }
+            Token::Colon => {
+                if colons == 0 {
+                    colon_start = position;
+                }
+                colons += 1;
+            }
+            _ => colons = 0,
+        };
+    }
+    stripped_selector.push_str(&selector[start..]);
+
+    // A selector that was only pseudo-classes, such as `p > :hover`, matches any element.
+    if stripped_selector.is_empty()
+        || stripped_selector.ends_with(|c: char| c.is_whitespace() || ">+~".contains(c))
+    {
+        stripped_selector.push('*');
+    }
+
+    stripped_selector
+}
+
+/// Returns where a top-level rule that can be preserved is in the CSS stylesheet.
+fn css_rule_location(css_rule: &CssRule, read_guard: &SharedRwLockReadGuard) -> Option<Location> {
+    let source_location = match *css_rule {
+        CssRule::Style(ref rule) => rule.as_ref().read_with(read_guard).source_location,
+        CssRule::Media(ref rule) => rule.as_ref().read_with(read_guard).source_location,
+        CssRule::FontFace(ref rule) => rule.as_ref().read_with(read_guard).source_location,
+        CssRule::Keyframes(ref rule) => rule.as_ref().read_with(read_guard).source_location,
+        CssRule::Supports(ref rule) => rule.as_ref().read_with(read_guard).source_location,
+        _ => return None,
+    };
+
+    Some(Location::from(source_location))
+}
+
+/// Collects CSS rules from the CSS stylesheet into `rules`.
+/// Optionally removes any excluded CSS properties.
+/// Optionally preserves `@media`, `@font-face`, `@font-feature-values`, `@keyframes`, `@page`, and
+/// `@supports` rules, and style rules with stateful pseudo-classes.
+pub(crate) fn collect_stylesheet_rules(
+    stylesheet: &Stylesheet,
+    options: &ConcreteOptions,
+    settings: &ConcreteSettings,
+    rules: &mut Rules,
+) {
+    let read_guard = &stylesheet.shared_lock.read();
+    let mut unparsed_rules = std::mem::take(&mut rules.unparsed)
+        .into_iter()
+        .filter(|(_, rule)| match rule.kind {
+            PreservedRuleKind::FontFeatureValues => options.preserve_font_feature_values,
+            PreservedRuleKind::Page => options.preserve_pages,
+            _ => true,
+        })
+        .peekable();
+    let mut style_rules = vec![];
+    for css_rule in &stylesheet.contents.rules.as_ref().read_with(read_guard).0 {
+        // Rules that servo couldn't parse go before the first rule after them in the CSS.
+        if let Some(location) = css_rule_location(css_rule, read_guard) {
+            while let Some(&(unparsed_location, _)) = unparsed_rules.peek() {
+                if unparsed_location > location {
+                    break;
+                }
+                if let Some((_, unparsed_rule)) = unparsed_rules.next() {
+                    rules.preserved.push(unparsed_rule);
+                }
+            }
+        }
+
+        match *css_rule {
+            CssRule::Style(ref style_rule_locked) => {
+                let style_rule = style_rule_locked.as_ref().read_with(read_guard);
+                let CssStyleRule {
+                    ref selectors,
+                    block: ref block_locked,
+                    source_location,
+                    ..
+                } = *style_rule;
+
+                use servo_css_parser::cssparser::ToCss;
+                let mut block = block_locked.as_ref().read_with(read_guard).clone();
+                block.remove_excluded_properties(&settings.excluded_properties);
+                let (source, location) = rules.source_map.resolve(Location::from(source_location));
+
+                // Every selector in a list has its own specificity, so each one is collected as
+                // a separate rule. Stateful selectors never match while inlining, so they can
+                // only be preserved.
+                let mut stateful_selectors = vec![];
+                for selector in selectors.0.iter() {
+                    let selector_css = selector.to_css_string();
+                    if is_stateful_selector(&selector_css, &settings.stateful_pseudo_classes) {
+                        stateful_selectors.push(selector_css);
+                        continue;
+                    }
+
+                    // kuchiki can't match `:is()`, `:where()`, or `:not()` with a list, so they
+                    // are expanded into selectors it can match, which keep the specificity of the
+                    // selector they were expanded from.
+                    let expanded_selectors = expand_selector(&selector_css);
+                    let specificity =
+                        if expanded_selectors.len() == 1 && expanded_selectors[0] == selector_css {
+                            selector.specificity()
+                        } else {
+                            selector_specificity(&selector_css)
+                        };
+
+                    for expanded_selector in expanded_selectors {
+                        style_rules.push(StyleRule {
+                            selector: expanded_selector,
+                            specificity,
+                            block: block.clone(),
+                            location,
+                            source: source.clone(),
+                        });
+                    }
+                }
+
+                if !options.preserve_pseudo_classes
+                    || stateful_selectors.is_empty()
+                    || block.is_empty()
+                {
+                    continue;
+                }
+
+                let mut declarations = String::default();
+                if block.to_css(&mut declarations).is_ok() {
+                    rules.preserved.push(PreservedRule {
+                        kind: PreservedRuleKind::PseudoClass,
+                        css: format!("{} {{ {} }}", stateful_selectors.join(", "), declarations),
+                        media: None,
+                    });
+                }
+            }
+
+            CssRule::Media(ref media_rule_locked) => {
+                if !options.preserve_media_queries {
+                    continue;
+                }
+
+                let media_rule = media_rule_locked.as_ref().read_with(read_guard);
+
+                use servo_css_parser::{cssparser::ToCss, style::shared_lock::ToCssWithGuard};
+                let css = media_rule.to_css_string(read_guard);
+
+                // The media query list is everything between `@media` and the block.
+                let query = css[..css.find('{').unwrap_or(css.len())]
+                    .trim_start_matches("@media")
+                    .trim()
+                    .to_string();
+
+                let nested_rules = media_rule
+                    .rules
+                    .as_ref()
+                    .read_with(read_guard)
+                    .0
+                    .iter()
+                    .map(|nested_rule| {
+                        let selectors = match *nested_rule {
+                            CssRule::Style(ref style_rule_locked) => style_rule_locked
+                                .as_ref()
+                                .read_with(read_guard)
+                                .selectors
+                                .0
+                                .iter()
+                                .map(|selector| selector.to_css_string())
+                                .collect(),
+                            _ => vec![],
+                        };
+
+                        NestedRule {
+                            selectors,
+                            css: nested_rule.to_css_string(read_guard),
+                        }
+                    })
+                    .collect();
+
+                rules.preserved.push(PreservedRule {
+                    kind: PreservedRuleKind::Media,
+                    css,
+                    media: Some(MediaBlock {
+                        query,
+                        rules: nested_rules,
+                    }),
+                });
+            }
+
+            CssRule::FontFace(ref font_face_rule_data_locked) => {
+                if !options.preserve_font_faces {
+                    continue;
+                }
+
+                let font_face_rule_data = font_face_rule_data_locked.as_ref().read_with(read_guard);
+
+                use servo_css_parser::style::shared_lock::ToCssWithGuard;
+                rules.preserved.push(PreservedRule {
+                    kind: PreservedRuleKind::FontFace,
+                    css: font_face_rule_data.to_css_string(read_guard),
+                    media: None,
+                });
+            }
+
+            CssRule::Keyframes(ref keyframes_rule_locked) => {
+                if !options.preserve_keyframes {
+                    continue;
+                }
+
+                let keyframes_rule = keyframes_rule_locked.as_ref().read_with(read_guard);
+
+                use servo_css_parser::style::shared_lock::ToCssWithGuard;
+                rules.preserved.push(PreservedRule {
+                    kind: PreservedRuleKind::Keyframes,
+                    css: keyframes_rule.to_css_string(read_guard),
+                    media: None,
+                });
+            }
+
+            CssRule::Supports(ref supports_rule_locked) => {
+                if !options.preserve_supports {
+                    continue;
+                }
+
+                let supports_rule = supports_rule_locked.as_ref().read_with(read_guard);
+
+                use servo_css_parser::style::shared_lock::ToCssWithGuard;
+                rules.preserved.push(PreservedRule {
+                    kind: PreservedRuleKind::Supports,
+                    css: supports_rule.to_css_string(read_guard),
+                    media: None,
+                });
+            }
+
+            _ => (),
+        }
+    }
+
+    rules
+        .preserved
+        .extend(unparsed_rules.map(|(_, unparsed_rule)| unparsed_rule));
+
+    // Style rules shared with a compiled stylesheet are only copied when the HTML document has
+    // style rules of its own.
+    if !style_rules.is_empty() {
+        Arc::make_mut(&mut rules.style).extend(style_rules);
+    }
+}
+
+/// A CSS stylesheet that is parsed, and has its rules collected, once so that it can be inlined
+/// into many HTML documents. It can be shared between threads, for example with `Arc`.
+#[derive(Clone, Debug)]
+pub struct CompiledStylesheet {
+    /// Options for ways to modify HTML documents using CSS.
+    pub options: ConcreteOptions,
+
+    /// Settings referenced by features enabled through options.
+    pub settings: ConcreteSettings,
+
+    /// Data collected from the CSS stylesheet.
+    pub rules: Rules,
+
+    /// Problems found while compiling, such as CSS that could not be parsed.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CompiledStylesheet {
+    /// Create a new compiled stylesheet from CSS, using concreate options and settings.
+    ///
+    /// `@import` rules in the CSS are replaced with the CSS they import, loaded with
+    /// `Settings.stylesheet_loader`. Any CSS that can't be parsed or loaded is ignored and
+    /// recorded in `diagnostics`.
+    pub fn new<T: Into<ConcreteOptions>, U: Into<ConcreteSettings>>(
+        css: &str,
+        options: Option<T>,
+        settings: Option<U>,
+    ) -> Self {
+        let settings = into_settings(settings);
+        let (url, url_diagnostic) = lenient_stylesheet_url(&settings);
+
+        let mut compiled_stylesheet = Self::compile(css, into_options(options), settings, url).0;
+        compiled_stylesheet.diagnostics.splice(0..0, url_diagnostic);
+
+        compiled_stylesheet
+    }
+
+    /// Create a new compiled stylesheet, the same way as `new`, but returns an error if any CSS
+    /// can't be parsed or loaded, any selector in `Options.insert_preserved_css` is invalid, or
+    /// any CSS property in `Settings` is unknown.
+    pub fn try_new<T: Into<ConcreteOptions>, U: Into<ConcreteSettings>>(
+        css: &str,
+        options: Option<T>,
+        settings: Option<U>,
+    ) -> Result<Self, Error> {
+        let options = into_options(options);
+        let settings = into_settings(settings);
+        validate(&options, &settings)?;
+
+        let url = stylesheet_url(&settings)?;
+        let (compiled_stylesheet, mut errors) = Self::compile(css, options, settings, url);
+        if !errors.is_empty() {
+            return Err(errors.remove(0));
+        }
+
+        Ok(compiled_stylesheet)
+    }
+
+    /// Parses the CSS and collects its rules, returning any errors found in the CSS.
+    fn compile(
+        css: &str,
+        options: ConcreteOptions,
+        settings: ConcreteSettings,
+        url: Url,
+    ) -> (Self, Vec<Error>) {
+        let mut errors = vec![];
+        let loader = settings.stylesheet_loader.as_deref();
+        let mut rules = Rules::default();
+        let mut joined_css = JoinedCss::default();
+        resolve_imports(
+            css,
+            &CssSource::Css,
+            loader,
+            &mut vec![],
+            &mut rules.preserved,
+            &mut errors,
+            &mut joined_css,
+        );
+
+        let mut diagnostics = errors.iter().cloned().map(Diagnostic::from).collect();
+        let (stylesheet, unparsed_rules) = parse_stylesheet(
+            &joined_css.css,
+            url,
+            &joined_css.source_map,
+            &mut errors,
+            &mut diagnostics,
+        );
+
+        rules.unparsed = unparsed_rules;
+        rules.source_map = joined_css.source_map;
+        collect_stylesheet_rules(&stylesheet, &options, &settings, &mut rules);
+
+        let compiled_stylesheet = Self {
+            options,
+            settings,
+            rules,
+            diagnostics,
+        };
+
+        (compiled_stylesheet, errors)
+    }
+}
+
+pub(crate) fn into_options<T: Into<ConcreteOptions>>(options: Option<T>) -> ConcreteOptions {
+    match options {
+        Some(o) => o.into(),
+        None => ConcreteOptions::default(),
+    }
+}
+
+pub(crate) fn into_settings<U: Into<ConcreteSettings>>(settings: Option<U>) -> ConcreteSettings {
+    match settings {
+        Some(s) => s.into(),
+        None => ConcreteSettings::default(),
+    }
+}
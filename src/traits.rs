@@ -10,9 +10,14 @@ pub trait ApplyRules {
     fn apply_rules(self: &mut Self) -> &mut Self;
 }
 
-/// Apply a specified CSS property as an attribute to elements.
+/// Insert elements for CSS rules that target the `::before` and `::after` pseudo-elements.
+pub trait ApplyPseudoElements {
+    fn apply_pseudo_elements(self: &mut Self) -> &mut Self;
+}
+
+/// Apply a specified CSS property as an attribute to specified elements.
 pub trait ApplyAttributes {
-    fn apply_attributes(self: &Self, property: &str) -> &Self;
+    fn apply_attributes(self: &Self, property: &str, elements: &[String]) -> &Self;
 }
 
 /// Apply a width attribute to elements.
@@ -30,7 +35,32 @@ pub trait ApplyTableElementAttributes {
     fn apply_table_element_attributes(self: &Self) -> &Self;
 }
 
+/// Remove style rules that don't match the HTML document from preserved `@media` rules.
+pub trait PruneMediaQueries {
+    fn prune_media_queries(self: &mut Self) -> &mut Self;
+}
+
+/// Resolve relative URLs in the HTML document and preserved CSS rules.
+pub trait AbsolutizeUrls {
+    fn absolutize_urls(self: &mut Self) -> &mut Self;
+}
+
 /// Insert preserved CSS rules as a `<style />` tag to a specified node.
 pub trait InsertPreservedCss {
     fn insert_preserved_css(self: &Self) -> &Self;
 }
+
+/// Remove placeholders for preserved CSS rules that nothing was inserted into.
+pub trait RemovePreservedCssPlaceholders {
+    fn remove_preserved_css_placeholders(self: &Self) -> &Self;
+}
+
+/// Remove attributes that control how elements are inlined from the HTML document.
+pub trait RemoveControlAttributes {
+    fn remove_control_attributes(self: &Self) -> &Self;
+}
+
+/// Measure the size of the HTML document and check it against a size budget.
+pub trait CheckSizeBudget {
+    fn check_size_budget(self: &mut Self, html: &str, preserved_css: &str) -> &mut Self;
+}
@@ -0,0 +1,93 @@
+use servo_css_parser::{
+    cssparser::{serialize_string, ParseError, Parser, ParserInput, Token},
+    types::Url,
+};
+
+/// Delimiters of template tags, such as `{{unsubscribe}}` or Mailchimp's `*|UNSUB|*`, that an
+/// email service provider replaces before sending.
+const TEMPLATE_DELIMITERS: &[&str] = &["{{", "{%", "*|", "%%", "<%", "[["];
+
+/// Resolves a URL against `base_url`. Returns `None` when the URL should be left as it is: when
+/// it is empty, only a `#fragment`, already absolute, can't be resolved, or has a template tag,
+/// which would be URL-encoded and no longer be replaced.
+pub fn absolutize_url(url: &str, base_url: &Url) -> Option<String> {
+    let url = url.trim();
+    if url.is_empty()
+        || url.starts_with('#')
+        || TEMPLATE_DELIMITERS
+            .iter()
+            .any(|delimiter| url.contains(delimiter))
+        || Url::parse(url).is_ok()
+    {
+        return None;
+    }
+
+    base_url.join(url).ok().map(|u| u.as_str().to_string())
+}
+
+/// Finds the `url()` values in CSS, including those nested in blocks and functions, as the byte
+/// range of each `url()` and the URL inside of it.
+fn find_urls(parser: &mut Parser, urls: &mut Vec<(usize, usize, String)>) {
+    loop {
+        let start = parser.position().byte_index();
+        let token = match parser.next_including_whitespace_and_comments() {
+            Ok(t) => t.clone(),
+            Err(_) => break,
+        };
+
+        match token {
+            Token::UnquotedUrl(ref url) => {
+                urls.push((start, parser.position().byte_index(), url.to_string()));
+            }
+            Token::Function(ref name) if name.eq_ignore_ascii_case("url") => {
+                let url = parser.parse_nested_block(|p| {
+                    p.expect_string()
+                        .map(|url| url.to_string())
+                        .map_err(ParseError::<()>::from)
+                });
+                if let Ok(url) = url {
+                    urls.push((start, parser.position().byte_index(), url));
+                }
+            }
+            Token::Function(_)
+            | Token::ParenthesisBlock
+            | Token::SquareBracketBlock
+            | Token::CurlyBracketBlock => {
+                let _ = parser.parse_nested_block(|p| {
+                    find_urls(p, urls);
+                    Ok::<_, ParseError<()>>(())
+                });
+            }
+            _ => {}
+        };
+    }
+}
+
+/// Resolves every relative `url()` in CSS against `base_url`, the same way as `absolutize_url`.
+/// Resolved URLs are always written as quoted strings, such as `url("https://example.com/a.png")`.
+pub fn absolutize_css_urls(css: &str, base_url: &Url) -> String {
+    let mut input = ParserInput::new(css);
+    let mut parser = Parser::new(&mut input);
+    let mut urls = vec![];
+    find_urls(&mut parser, &mut urls);
+
+    let mut absolutized_css = String::default();
+    let mut end_of_last_url = 0;
+    for (start, end, url) in urls {
+        let absolute_url = match absolutize_url(&url, base_url) {
+            Some(u) => u,
+            None => continue,
+        };
+
+        absolutized_css.push_str(&css[end_of_last_url..start]);
+        absolutized_css.push_str("url(");
+        if serialize_string(&absolute_url, &mut absolutized_css).is_err() {
+            return css.to_string();
+        }
+        absolutized_css.push(')');
+        end_of_last_url = end;
+    }
+    absolutized_css.push_str(&css[end_of_last_url..]);
+
+    absolutized_css
+}
@@ -0,0 +1,129 @@
+use eyeliner::{inline, AbstractOptions, AbstractSettings};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const CSS: &str = r#"
+    .hero { background-image: url(images/hero.png); }
+
+    @media (max-width: 600px) {
+        .hero { background-image: url(images/hero-small.png); }
+    }
+"#;
+
+fn options() -> Option<AbstractOptions> {
+    Some(AbstractOptions {
+        absolutize_urls: Some(true),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn absolutize_urls_with_base_url() {
+    let html = r##"
+        <html>
+            <head></head>
+            <body>
+                <div class="hero"><img src="images/logo.png"></div>
+                <a href="unsubscribe?id=1">Unsubscribe</a>
+                <a href="#top">Top</a>
+                <a href="https://example.org/">Elsewhere</a>
+                <a href="{{unsubscribe_url}}">Unsubscribe</a>
+                <a href="*|UNSUB|*">Unsubscribe</a>
+                <table><tr><td background="images/texture.png">Cell</td></tr></table>
+                <table><tr><td class="hero">Cell</td></tr></table>
+            </body>
+        </html>
+    "##;
+    let settings = AbstractSettings {
+        base_url: Some(String::from("https://example.com/emails/")),
+        ..Default::default()
+    };
+    let result_document = parse_html().one(inline(
+        html,
+        Some(CSS.to_string()),
+        options(),
+        Some(settings),
+    ));
+
+    let img = result_document.select_first("img").unwrap();
+    assert_eq!(
+        img.attributes.borrow().get("src"),
+        Some("https://example.com/emails/images/logo.png")
+    );
+
+    let hrefs = result_document
+        .select("a")
+        .unwrap()
+        .map(|a| a.attributes.borrow().get("href").unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        hrefs,
+        vec![
+            "https://example.com/emails/unsubscribe?id=1",
+            "#top",
+            "https://example.org/",
+            "{{unsubscribe_url}}",
+            "*|UNSUB|*",
+        ]
+    );
+
+    let td = result_document.select_first("td").unwrap();
+    assert_eq!(
+        td.attributes.borrow().get("background"),
+        Some("https://example.com/emails/images/texture.png")
+    );
+
+    // `background-image` is mapped to the `background` attribute before URLs are absolutized.
+    let hero_td = result_document.select_first("td.hero").unwrap();
+    assert_eq!(
+        hero_td.attributes.borrow().get("background"),
+        Some("https://example.com/emails/images/hero.png")
+    );
+
+    let hero = result_document.select_first(".hero").unwrap();
+    assert!(hero
+        .attributes
+        .borrow()
+        .get("style")
+        .unwrap()
+        .contains(r#"url("https://example.com/emails/images/hero.png")"#));
+
+    let preserved_css = result_document
+        .select_first("style")
+        .unwrap()
+        .text_contents();
+    assert!(preserved_css.contains(r#"url("https://example.com/emails/images/hero-small.png")"#));
+}
+
+#[test]
+fn absolutize_urls_with_base_tag() {
+    let html = r#"
+        <html>
+            <head><base href="https://cdn.example.com/assets/"></head>
+            <body><img src="logo.png"></body>
+        </html>
+    "#;
+    let result_document = parse_html().one(inline(html, None, options(), None));
+
+    assert!(result_document.select_first("base").is_err());
+    let img = result_document.select_first("img").unwrap();
+    assert_eq!(
+        img.attributes.borrow().get("src"),
+        Some("https://cdn.example.com/assets/logo.png")
+    );
+}
+
+#[test]
+fn leave_relative_urls() {
+    let html = r#"
+        <html>
+            <head><base href="https://cdn.example.com/assets/"></head>
+            <body><img src="logo.png"></body>
+        </html>
+    "#;
+    let result_document = parse_html().one(inline(html, None, None, None));
+
+    assert!(result_document.select_first("base").is_ok());
+    let img = result_document.select_first("img").unwrap();
+    assert_eq!(img.attributes.borrow().get("src"), Some("logo.png"));
+}
@@ -0,0 +1,103 @@
+use eyeliner::{inline, AbstractOptions};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const HTML: &str = r#"
+    <html>
+        <head>
+            <style>
+                @keyframes fade {
+                    from { opacity: 0; }
+                    to { opacity: 1; }
+                }
+
+                @supports (display: flex) {
+                    .row { display: flex; }
+                }
+
+                .row { animation: fade 1s; }
+            </style>
+        </head>
+        <body>
+            <div class="row">Hello</div>
+        </body>
+    </html>
+"#;
+
+#[test]
+fn preserve_at_rules() {
+    let result_document = parse_html().one(inline(HTML, None, None, None));
+
+    let preserved_css = result_document
+        .select_first("head > style")
+        .unwrap()
+        .text_contents();
+    assert!(preserved_css.contains("@keyframes fade"));
+    assert!(preserved_css.contains("@supports (display: flex)"));
+}
+
+#[test]
+fn drop_at_rules() {
+    let options = AbstractOptions {
+        preserve_keyframes: Some(false),
+        preserve_supports: Some(false),
+        ..Default::default()
+    };
+    let result_document = parse_html().one(inline(HTML, None, Some(options), None));
+
+    assert!(result_document.select_first("style").is_err());
+}
+
+#[test]
+fn preserve_page_and_font_feature_values() {
+    let html = r#"
+        <html>
+            <head>
+                <style>
+                    @font-feature-values Font One {
+                        @styleset { nice-style: 12; }
+                    }
+
+                    @media print {
+                        .row { color: black; }
+                    }
+
+                    @page :first {
+                        margin: 1in;
+                    }
+
+                    .row { font-family: "Font One"; }
+                </style>
+            </head>
+            <body>
+                <div class="row">Hello</div>
+            </body>
+        </html>
+    "#;
+
+    let result_document = parse_html().one(inline(html, None, None, None));
+
+    let preserved_css = result_document
+        .select_first("head > style")
+        .unwrap()
+        .text_contents();
+    let font_feature_values = preserved_css.find("@font-feature-values Font One").unwrap();
+    let media = preserved_css.find("@media print").unwrap();
+    let page = preserved_css.find("@page :first").unwrap();
+    assert!(font_feature_values < media && media < page);
+
+    let options = AbstractOptions {
+        preserve_font_feature_values: Some(false),
+        preserve_pages: Some(false),
+        ..Default::default()
+    };
+    let result_document = parse_html().one(inline(html, None, Some(options), None));
+
+    let preserved_css = result_document
+        .select_first("head > style")
+        .unwrap()
+        .text_contents();
+    assert!(preserved_css.contains("@media print"));
+    assert!(!preserved_css.contains("@font-feature-values"));
+    assert!(!preserved_css.contains("@page"));
+}
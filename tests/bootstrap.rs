@@ -0,0 +1,42 @@
+use eyeliner::{inline, AbstractOptions};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+fn options() -> Option<AbstractOptions> {
+    Some(AbstractOptions {
+        apply_pseudo_elements: Some(true),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn bootstrap_pseudo_elements() {
+    let html = include_str!("bootstrap.html");
+    let css = include_str!("bootstrap.css");
+
+    let result_document = parse_html().one(inline(html, Some(css.to_string()), options(), None));
+
+    // `.dropdown-toggle::after` draws the caret with borders and an empty `content`.
+    let caret = result_document
+        .select_first(".dropdown-toggle > span:last-child")
+        .unwrap();
+    let attributes = caret.attributes.borrow();
+    assert!(attributes.get("style").unwrap().contains("border-top"));
+    assert_eq!(caret.text_contents(), "");
+
+    let separators = result_document
+        .select(".breadcrumb-item + .breadcrumb-item > span:first-child")
+        .unwrap()
+        .map(|separator| separator.text_contents())
+        .collect::<Vec<_>>();
+    assert_eq!(separators, vec!["/", "/"]);
+
+    let dash = result_document
+        .select_first(".blockquote-footer > span:first-child")
+        .unwrap();
+    assert_eq!(dash.text_contents(), "\u{2014}\u{a0}");
+
+    // `<input>` is a void element, so `*::before` and `*::after` don't add elements to it.
+    let input = result_document.select_first("input").unwrap();
+    assert!(input.as_node().first_child().is_none());
+}
@@ -0,0 +1,54 @@
+use eyeliner::{
+    inline, inline_compiled, inline_document, AbstractOptions, AbstractSettings,
+    CompiledStylesheet, Eyeliner,
+};
+use std::{sync::Arc, thread};
+
+#[test]
+fn compiled() {
+    let css = include_str!("./test.css");
+    let compiled_stylesheet = Arc::new(CompiledStylesheet::new(
+        css,
+        None::<AbstractOptions>,
+        None::<AbstractSettings>,
+    ));
+
+    let threads = (0..4)
+        .map(|_| {
+            let compiled_stylesheet = Arc::clone(&compiled_stylesheet);
+            thread::spawn(move || {
+                inline_compiled(include_str!("./test.html"), &compiled_stylesheet)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let expected = inline(
+        include_str!("./test.html"),
+        Some(css.to_string()),
+        None,
+        None,
+    );
+
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), expected);
+    }
+}
+
+#[test]
+fn share_compiled_style_rules() {
+    let compiled_stylesheet = CompiledStylesheet::new(
+        include_str!("./test.css"),
+        None::<AbstractOptions>,
+        None::<AbstractSettings>,
+    );
+
+    // Without `<style>` tags, the HTML document has no style rules of its own to add.
+    let html = r#"<p id="bazz" class="foo bar">HTML + CSS + Eyeliner = Easy Emails</p>"#;
+    let mut eyeliner = Eyeliner::from_compiled(html, &compiled_stylesheet);
+    inline_document(&mut eyeliner);
+
+    assert!(Arc::ptr_eq(
+        &eyeliner.rules.style,
+        &compiled_stylesheet.rules.style
+    ));
+}
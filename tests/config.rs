@@ -0,0 +1,74 @@
+#![cfg(feature = "config")]
+
+use eyeliner::{inline, AbstractOptions, AbstractSettings, Config, Error};
+
+#[test]
+fn toml() {
+    let config = Config::from_toml(
+        r#"
+            [options]
+            remove_style_tags = false
+
+            [settings]
+            width_elements = ["td"]
+            root_font_size = 10
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.options.remove_style_tags, Some(false));
+    assert_eq!(config.options.preserve_important, None);
+    assert_eq!(
+        config.settings.width_elements,
+        Some(vec![String::from("td")])
+    );
+    assert_eq!(config.settings.root_font_size, Some(10.0));
+}
+
+#[test]
+fn json() {
+    let config = Config::from_json(r#"{ "options": { "apply_style_tags": false } }"#).unwrap();
+
+    assert_eq!(config.options.apply_style_tags, Some(false));
+    assert_eq!(config.settings.width_elements, None);
+}
+
+#[test]
+fn unknown_fields() {
+    match Config::from_toml("[options]\nremove_style_tag = false\n") {
+        Err(Error::Config { message, .. }) => assert!(message.contains("remove_style_tag")),
+        result => panic!("expected a config error, got {:?}", result),
+    }
+
+    match Config::from_json(r#"{ "setting": {} }"#) {
+        Err(Error::Config { message, .. }) => assert!(message.contains("setting")),
+        result => panic!("expected a config error, got {:?}", result),
+    }
+}
+
+#[test]
+fn merge() {
+    let config = Config::from_toml(
+        r#"
+            [options]
+            remove_style_tags = false
+            preserve_important = true
+        "#,
+    )
+    .unwrap()
+    .merge(Config {
+        options: AbstractOptions {
+            preserve_important: Some(false),
+            ..Default::default()
+        },
+        settings: AbstractSettings::default(),
+    });
+
+    assert_eq!(config.options.remove_style_tags, Some(false));
+    assert_eq!(config.options.preserve_important, Some(false));
+
+    let html = "<style>p { color: red; }</style><p>Hello</p>";
+    let inlined_html = inline(html, None, Some(config.options), Some(config.settings));
+    assert!(inlined_html.contains("<style>"));
+    assert!(inlined_html.contains(r#"<p style="color: red;">"#));
+}
@@ -0,0 +1,98 @@
+use eyeliner::inline;
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const CSS: &str = r#"
+    p { color: red; }
+"#;
+
+#[test]
+fn embedded_style_tag() {
+    let html = r#"
+        <html>
+            <head>
+                <style data-embed>p { font-weight: bold; }</style>
+            </head>
+            <body><p>Hello</p></body>
+        </html>
+    "#;
+    let result_document = parse_html().one(inline(html, Some(CSS.to_string()), None, None));
+
+    let style_nodes = result_document.select("style").unwrap().collect::<Vec<_>>();
+    assert_eq!(style_nodes.len(), 1);
+    assert_eq!(style_nodes[0].text_contents(), "p { font-weight: bold; }");
+    assert!(!style_nodes[0].attributes.borrow().contains("data-embed"));
+
+    let p = result_document.select_first("p").unwrap();
+    assert_eq!(p.attributes.borrow().get("style"), Some("color: red;"));
+}
+
+#[test]
+fn kept_style_tag() {
+    let html = r#"
+        <html>
+            <head>
+                <style data-eyeliner-keep>p { font-weight: bold; }</style>
+            </head>
+            <body><p>Hello</p></body>
+        </html>
+    "#;
+    let result_document = parse_html().one(inline(html, None, None, None));
+
+    let style_nodes = result_document.select("style").unwrap().collect::<Vec<_>>();
+    assert_eq!(style_nodes.len(), 1);
+    assert!(!style_nodes[0]
+        .attributes
+        .borrow()
+        .contains("data-eyeliner-keep"));
+
+    let p = result_document.select_first("p").unwrap();
+    assert_eq!(
+        p.attributes.borrow().get("style"),
+        Some("font-weight: bold;")
+    );
+}
+
+#[test]
+fn ignored_elements() {
+    let html = r#"
+        <html>
+            <head></head>
+            <body>
+                <p id="inlined">Hello</p>
+                <div data-eyeliner-ignore>
+                    <p id="ignored">World</p>
+                </div>
+            </body>
+        </html>
+    "#;
+    let result_document = parse_html().one(inline(html, Some(CSS.to_string()), None, None));
+
+    let inlined = result_document.select_first("#inlined").unwrap();
+    assert_eq!(
+        inlined.attributes.borrow().get("style"),
+        Some("color: red;")
+    );
+
+    let ignored = result_document.select_first("#ignored").unwrap();
+    assert_eq!(ignored.attributes.borrow().get("style"), None);
+
+    let div = result_document.select_first("div").unwrap();
+    assert!(!div.attributes.borrow().contains("data-eyeliner-ignore"));
+}
+
+#[test]
+fn other_elements_keep_attributes() {
+    let html = r#"
+        <html>
+            <head></head>
+            <body><p data-embed="video" data-eyeliner-keep>Hello</p></body>
+        </html>
+    "#;
+    let result_document = parse_html().one(inline(html, Some(CSS.to_string()), None, None));
+
+    let p = result_document.select_first("p").unwrap();
+    let attributes = p.attributes.borrow();
+    assert_eq!(attributes.get("data-embed"), Some("video"));
+    assert!(attributes.contains("data-eyeliner-keep"));
+}
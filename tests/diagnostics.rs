@@ -0,0 +1,174 @@
+use eyeliner::{
+    inline_document, traits::*, AbstractOptions, AbstractSettings, CompiledStylesheet, CssSource,
+    Eyeliner, Location, MemoryLoader, Severity,
+};
+use maplit::hashmap;
+use std::sync::Arc;
+
+#[test]
+fn diagnostics() {
+    let html = r#"<p class="red">Hello, world!</p>"#;
+    let css = r#"
+        .red {
+            color: ;
+        }
+
+        .red::after {
+            content: "!";
+        }
+
+        .red {
+            mso-line-height-rule: exactly;
+        }
+    "#;
+
+    let mut eyeliner = Eyeliner::new(
+        html,
+        Some(css.to_string()),
+        None::<AbstractOptions>,
+        None::<AbstractSettings>,
+    );
+    eyeliner.collect_rules().apply_rules();
+
+    let errors = eyeliner
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].location.map(|l| l.line), Some(3));
+
+    let warnings = eyeliner
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Warning)
+        .collect::<Vec<_>>();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].location.map(|l| l.line), Some(11));
+    assert_eq!(warnings[1].location, Some(Location { line: 6, column: 9 }));
+}
+
+#[test]
+fn inline_document_diagnostics() {
+    let html = r#"
+        <html>
+            <head>
+                <style>
+                    .red {
+                        colr: red;
+                    }
+
+                    ..red {
+                        color: red;
+                    }
+                </style>
+            </head>
+            <body>
+                <p class="red">Hello, world!</p>
+            </body>
+        </html>
+    "#;
+    let css = r#"
+        @media (max-width: 600px) {
+            .red {
+                color: blue;
+            }
+        }
+    "#;
+
+    let compiled_stylesheet = CompiledStylesheet::new(
+        css,
+        None::<AbstractOptions>,
+        Some(AbstractSettings {
+            size_budget: Some(1),
+            ..AbstractSettings::default()
+        }),
+    );
+    let mut eyeliner = Eyeliner::from_compiled(html, &compiled_stylesheet);
+    let result = inline_document(&mut eyeliner);
+
+    assert!(result.contains("@media (max-width: 600px)"));
+
+    let severities = eyeliner
+        .diagnostics
+        .iter()
+        .map(|d| d.severity)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        severities,
+        vec![Severity::Warning, Severity::Error, Severity::Warning]
+    );
+}
+
+#[test]
+fn invalid_base_url() {
+    let html = r#"<img src="logo.png">"#;
+    let settings = AbstractSettings {
+        base_url: Some(String::from("emails/")),
+        ..AbstractSettings::default()
+    };
+
+    let eyeliner = Eyeliner::new(html, None, None::<AbstractOptions>, Some(settings.clone()));
+    assert_eq!(eyeliner.diagnostics.len(), 1);
+    assert_eq!(eyeliner.diagnostics[0].severity, Severity::Warning);
+    assert!(eyeliner.diagnostics[0]
+        .message
+        .contains("Settings.base_url"));
+
+    let compiled_stylesheet = CompiledStylesheet::new("", None::<AbstractOptions>, Some(settings));
+    let eyeliner = Eyeliner::from_compiled(html, &compiled_stylesheet);
+    assert_eq!(eyeliner.diagnostics, compiled_stylesheet.diagnostics);
+    assert_eq!(eyeliner.diagnostics.len(), 1);
+}
+
+#[test]
+fn diagnostic_sources() {
+    let html = r#"
+        <html>
+            <head>
+                <style></style>
+                <style>
+                    .red {
+                        color: ;
+                    }
+                </style>
+            </head>
+            <body>
+                <p class="red">Hello, world!</p>
+            </body>
+        </html>
+    "#;
+    let css = r#"@import "colors.css";
+        .red {
+            color: ;
+        }
+    "#;
+    let settings = AbstractSettings {
+        stylesheet_loader: Some(Arc::new(MemoryLoader::new(hashmap! {
+            "colors.css".to_owned() => ".red {\n    color: ;\n}".to_owned(),
+        }))),
+        ..AbstractSettings::default()
+    };
+
+    let eyeliner = Eyeliner::new(
+        html,
+        Some(css.to_string()),
+        None::<AbstractOptions>,
+        Some(settings),
+    );
+
+    let errors = eyeliner
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| (d.source.clone(), d.location.map(|l| l.line)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (Some(CssSource::Href(String::from("colors.css"))), Some(2)),
+            (Some(CssSource::Css), Some(3)),
+            (Some(CssSource::StyleTag(1)), Some(3)),
+        ]
+    );
+}
@@ -0,0 +1,38 @@
+use eyeliner::inline;
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+#[test]
+fn dimensions() {
+    let expected_document = parse_html().one(include_str!("./dimensions_fixture.html"));
+    let result_document = parse_html().one(inline(
+        include_str!("./dimensions.html"),
+        Some(include_str!("./dimensions.css").to_string()),
+        None,
+        None,
+    ));
+
+    let selector = "[id]";
+    let expected_select = expected_document.select(selector).unwrap();
+    let result_select = result_document.select(selector).unwrap();
+
+    for (expected_node, result_node) in expected_select.zip(result_select) {
+        let expected_attributes = expected_node.attributes.borrow();
+        let result_attributes = result_node.attributes.borrow();
+
+        for attribute in &["width", "height"] {
+            println!(
+                "\n#{} {}\nExpected:\t{:?}\nResult:  \t{:?}",
+                expected_attributes.get("id").unwrap(),
+                attribute,
+                expected_attributes.get(*attribute),
+                result_attributes.get(*attribute),
+            );
+
+            assert_eq!(
+                result_attributes.get(*attribute),
+                expected_attributes.get(*attribute)
+            );
+        }
+    }
+}
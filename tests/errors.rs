@@ -0,0 +1,57 @@
+use eyeliner::{try_inline, AbstractOptions, CssSource, Error};
+
+#[test]
+fn invalid_css() {
+    let html = r#"<p class="red">Hello, world!</p>"#;
+    let css = r#"
+        .red {
+            color: ;
+        }
+    "#;
+
+    match try_inline(html, Some(css.to_string()), None, None) {
+        Err(Error::Css { source, line, .. }) => {
+            assert_eq!(source, CssSource::Css);
+            assert_eq!(line, 3);
+        }
+        result => panic!("Expected a CSS error, got {:?}", result),
+    }
+}
+
+#[test]
+fn unknown_properties() {
+    let html = r#"<p class="red">Hello, world!</p>"#;
+    let css = r#"
+        .red {
+            color: red;
+            mso-line-height-rule: exactly;
+        }
+    "#;
+
+    assert!(try_inline(html, Some(css.to_string()), None, None).is_ok());
+}
+
+#[test]
+fn invalid_selector() {
+    let options = AbstractOptions {
+        insert_preserved_css: Some(vec!["head >".to_owned()]),
+        ..AbstractOptions::default()
+    };
+
+    assert_eq!(
+        try_inline("<p>Hello, world!</p>", None, Some(options), None),
+        Err(Error::InvalidSelector("head >".to_owned())),
+    );
+}
+
+#[test]
+fn valid() {
+    let html = r#"<p class="red">Hello, world!</p>"#;
+    let css = r#"
+        .red {
+            color: red;
+        }
+    "#;
+
+    assert!(try_inline(html, Some(css.to_string()), None, None).is_ok());
+}
@@ -0,0 +1,60 @@
+use eyeliner::{inline_fragment, traits::*, AbstractOptions, AbstractSettings, Eyeliner};
+
+const CSS: &str = r#"
+    .title { color: red; }
+    td { padding: 4px; }
+
+    @media (max-width: 600px) {
+        .title { font-size: 20px; }
+    }
+"#;
+
+#[test]
+fn inline_fragment_without_document() {
+    let html = r#"<h1 class="title">Hello</h1><p>World</p>"#;
+    let inlined_html = inline_fragment(html, Some(CSS.to_string()), None, None);
+
+    assert_eq!(
+        inlined_html,
+        r#"<h1 class="title" style="color: red;">Hello</h1><p>World</p>"#
+    );
+}
+
+#[test]
+fn fragment_context_element() {
+    let html = r#"<td>Cell</td>"#;
+    let settings = AbstractSettings {
+        fragment_context_element: Some(String::from("tr")),
+        ..Default::default()
+    };
+    let inlined_html = inline_fragment(html, Some(CSS.to_string()), None, Some(settings));
+
+    assert_eq!(inlined_html, r#"<td style="padding: 4px;">Cell</td>"#);
+}
+
+#[test]
+fn preserved_css() {
+    let html = r#"<h1 class="title">Hello</h1>"#;
+    let settings = AbstractSettings {
+        fragment_context_element: Some(String::from("body")),
+        ..Default::default()
+    };
+
+    let mut eyeliner = Eyeliner::new(
+        html,
+        Some(CSS.to_string()),
+        None::<AbstractOptions>,
+        Some(settings),
+    );
+    eyeliner
+        .collect_rules()
+        .apply_rules()
+        .insert_preserved_css();
+
+    let inlined_html = eyeliner.to_string();
+    assert!(!inlined_html.contains("<style"));
+    assert!(!inlined_html.contains("<html"));
+    assert!(eyeliner
+        .preserved_css()
+        .contains("@media (max-width: 600px)"));
+}
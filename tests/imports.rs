@@ -0,0 +1,118 @@
+use eyeliner::{inline, try_inline, AbstractSettings, Error, MemoryLoader};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+use maplit::hashmap;
+use std::sync::Arc;
+
+fn settings() -> AbstractSettings {
+    AbstractSettings {
+        stylesheet_loader: Some(Arc::new(MemoryLoader::new(hashmap! {
+            "design/index.css".to_owned() => r#"
+                @import "partials/colors.css";
+                @import url(partials/mobile.css) (max-width: 600px);
+
+                .bold {
+                    font-weight: bold;
+                }
+            "#.to_owned(),
+            "design/partials/colors.css".to_owned() => ".red { color: red; }".to_owned(),
+            "design/partials/mobile.css".to_owned() => ".red { color: blue; }".to_owned(),
+            "cycle/a.css".to_owned() => r#"@import "b.css";"#.to_owned(),
+            "cycle/b.css".to_owned() => r#"@import "a.css";"#.to_owned(),
+        }))),
+        ..AbstractSettings::default()
+    }
+}
+
+#[test]
+fn imports() {
+    let html = r#"
+        <html>
+            <head></head>
+            <body>
+                <p class="red bold">Hello, world!</p>
+            </body>
+        </html>
+    "#;
+    let css = r#"@import "design/index.css";"#;
+
+    let result = inline(html, Some(css.to_owned()), None, Some(settings()));
+    let result_document = parse_html().one(result);
+
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    assert_eq!(
+        result_attributes.get("style"),
+        Some("color: red; font-weight: bold;")
+    );
+
+    let result_style = result_document.select_first("style").unwrap();
+    assert!(result_style
+        .text_contents()
+        .contains("@media (max-width: 600px)"));
+}
+
+#[test]
+fn import_cycle() {
+    let css = r#"@import "cycle/a.css";"#;
+
+    assert_eq!(
+        try_inline("<p></p>", Some(css.to_owned()), None, Some(settings())),
+        Err(Error::ImportCycle(vec![
+            "cycle/a.css".to_owned(),
+            "cycle/b.css".to_owned(),
+            "cycle/a.css".to_owned(),
+        ])),
+    );
+}
+
+#[test]
+fn missing_import() {
+    let css = r#"@import "design/missing.css";"#;
+
+    match try_inline("<p></p>", Some(css.to_owned()), None, Some(settings())) {
+        Err(Error::Load { href, .. }) => assert_eq!(href, "design/missing.css"),
+        result => panic!("Expected a load error, got {:?}", result),
+    }
+}
+
+#[test]
+fn absolute_import() {
+    let css = r#"
+        @import url(https://fonts.googleapis.com/css?family=Roboto) screen;
+        @import "design/partials/colors.css";
+    "#;
+
+    let result = try_inline(
+        r#"<p class="red"></p>"#,
+        Some(css.to_owned()),
+        None,
+        Some(settings()),
+    )
+    .unwrap();
+    let result_document = parse_html().one(result);
+
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    assert_eq!(result_attributes.get("style"), Some("color: red;"));
+
+    let result_style = result_document.select_first("style").unwrap();
+    assert_eq!(
+        result_style.text_contents().trim(),
+        r#"@import "https://fonts.googleapis.com/css?family=Roboto" screen;"#
+    );
+}
+
+#[test]
+fn import_without_loader() {
+    let css = r#"@import "design/index.css";"#;
+
+    let result = try_inline("<p></p>", Some(css.to_owned()), None, None).unwrap();
+    let result_document = parse_html().one(result);
+
+    let result_style = result_document.select_first("style").unwrap();
+    assert_eq!(
+        result_style.text_contents().trim(),
+        r#"@import "design/index.css";"#
+    );
+}
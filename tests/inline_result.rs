@@ -0,0 +1,78 @@
+use eyeliner::{
+    inline, inline_compiled_result, inline_result, AbstractOptions, AbstractSettings,
+    CompiledStylesheet, Severity,
+};
+
+const HTML: &str = r#"
+    <html>
+        <head>
+            <title>Test</title>
+        </head>
+        <body>
+            <p class="red">Hello, world!</p>
+        </body>
+    </html>
+"#;
+
+const CSS: &str = r#"
+    .red { color: red; colr: blue; }
+
+    @media (max-width: 600px) {
+        .red { color: blue; }
+    }
+"#;
+
+#[test]
+fn inline_result_separates_preserved_css() {
+    let result = inline_result(HTML, Some(CSS.to_string()), None, None);
+
+    assert!(result
+        .html
+        .contains(r#"<p class="red" style="color: red;">"#));
+    assert!(!result.html.contains("<style>"));
+    assert!(result.preserved_css.contains("@media (max-width: 600px)"));
+    assert!(result
+        .diagnostics
+        .iter()
+        .any(|d| d.severity == Severity::Warning));
+
+    // `inline` still inserts the preserved CSS.
+    let inlined_html = inline(HTML, Some(CSS.to_string()), None, None);
+    assert!(inlined_html.contains("<style>"));
+    assert!(inlined_html.contains(&result.preserved_css));
+}
+
+#[test]
+fn inline_compiled_result_matches() {
+    let compiled_stylesheet =
+        CompiledStylesheet::new(CSS, None::<AbstractOptions>, None::<AbstractSettings>);
+
+    assert_eq!(
+        inline_compiled_result(HTML, &compiled_stylesheet),
+        inline_result(HTML, Some(CSS.to_string()), None, None),
+    );
+}
+
+#[test]
+fn inline_result_removes_placeholders() {
+    let html = r#"
+        <html>
+            <head>
+                <style data-eyeliner-preserve></style>
+                <!-- eyeliner:preserved -->
+                <style data-eyeliner-preserve>.existing { color: green; }</style>
+            </head>
+            <body>
+                <p class="red">Hello, world!</p>
+            </body>
+        </html>
+    "#;
+    let result = inline_result(html, Some(CSS.to_string()), None, None);
+
+    assert!(!result.html.contains("eyeliner:preserved"));
+    assert!(!result.html.contains("data-eyeliner-preserve"));
+    assert!(!result.html.contains("<style></style>"));
+    assert!(result
+        .html
+        .contains("<style>.existing { color: green; }</style>"));
+}
@@ -0,0 +1,115 @@
+use eyeliner::{inline, try_inline, AbstractSettings, Error, FileSystemLoader, MemoryLoader};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+use maplit::hashmap;
+use std::sync::Arc;
+
+#[test]
+fn memory_loader() {
+    let html = r#"
+        <html>
+            <head>
+                <link rel="stylesheet" href="red.css">
+                <link rel="stylesheet" href="ignored.css" data-eyeliner-ignore>
+            </head>
+            <body>
+                <p class="red">Hello, world!</p>
+            </body>
+        </html>
+    "#;
+
+    let settings = AbstractSettings {
+        stylesheet_loader: Some(Arc::new(MemoryLoader::new(hashmap! {
+            "red.css".to_owned() => ".red { color: red; }".to_owned(),
+        }))),
+        ..AbstractSettings::default()
+    };
+
+    let result_document = parse_html().one(inline(html, None, None, Some(settings)));
+
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    assert_eq!(result_attributes.get("style"), Some("color: red;"));
+
+    let result_links = result_document
+        .select("link")
+        .unwrap()
+        .map(|l| l.attributes.borrow().get("href").unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(result_links, vec!["ignored.css"]);
+}
+
+#[test]
+fn file_system_loader() {
+    let html = r#"<link rel="stylesheet" href="test.css"><p id="bazz">Hello, world!</p>"#;
+
+    let settings = AbstractSettings {
+        stylesheet_loader: Some(Arc::new(FileSystemLoader::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests"
+        )))),
+        ..AbstractSettings::default()
+    };
+
+    let result_document = parse_html().one(inline(html, None, None, Some(settings)));
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    assert_eq!(result_attributes.get("style"), Some("font-weight: normal;"));
+}
+
+#[test]
+fn missing_stylesheet() {
+    let html = r#"<link rel="stylesheet" href="../secret.css"><p>Hello, world!</p>"#;
+
+    let settings = AbstractSettings {
+        stylesheet_loader: Some(Arc::new(FileSystemLoader::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests"
+        )))),
+        ..AbstractSettings::default()
+    };
+
+    match try_inline(html, None, None, Some(settings)) {
+        Err(Error::Load { href, .. }) => assert_eq!(href, "../secret.css"),
+        result => panic!("Expected a load error, got {:?}", result),
+    }
+}
+
+#[test]
+fn absolute_stylesheet() {
+    let html = r#"
+        <html>
+            <head>
+                <link rel="stylesheet" href="https://fonts.googleapis.com/css?family=Roboto">
+                <link rel="stylesheet" href="test.css">
+            </head>
+            <body>
+                <p id="bazz">Hello, world!</p>
+            </body>
+        </html>
+    "#;
+
+    let settings = AbstractSettings {
+        stylesheet_loader: Some(Arc::new(FileSystemLoader::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests"
+        )))),
+        ..AbstractSettings::default()
+    };
+
+    let result_document = parse_html().one(try_inline(html, None, None, Some(settings)).unwrap());
+
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    assert_eq!(result_attributes.get("style"), Some("font-weight: normal;"));
+
+    let result_links = result_document
+        .select("link")
+        .unwrap()
+        .map(|l| l.attributes.borrow().get("href").unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        result_links,
+        vec!["https://fonts.googleapis.com/css?family=Roboto"]
+    );
+}
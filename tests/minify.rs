@@ -0,0 +1,125 @@
+use eyeliner::{inline, AbstractOptions};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const HTML: &str = r#"
+    <html>
+        <head>
+            <style>
+                h1 { color: #336699; margin: 0px auto; font: bold 12px / 1.5 Arial, sans-serif; }
+                p { border: 1px solid rgb(17, 34, 51); padding: calc(1em + 0px) !important; }
+
+                @media (max-width: 600px) {
+                    h1 > a, p a { color: rgb(0, 0, 170); }
+                }
+            </style>
+        </head>
+        <body>
+            <h1>Hello, <a href="https://example.com">world</a>!</h1>
+            <p style="background: #ffffff">Paragraph</p>
+        </body>
+    </html>
+"#;
+
+fn options(minify: bool) -> Option<AbstractOptions> {
+    Some(AbstractOptions {
+        minify: Some(minify),
+        preserve_important: Some(true),
+        ..Default::default()
+    })
+}
+
+/// Inlines `html` again, so that servo parses and serializes every `style` attribute and preserved
+/// CSS rule.
+fn reinline(html: &str) -> String {
+    inline(html, Some("* {}".to_string()), options(false), None)
+}
+
+#[test]
+fn minify() {
+    let result_document = parse_html().one(inline(HTML, None, options(true), None));
+
+    let h1 = result_document.select_first("h1").unwrap();
+    let h1_style = h1.attributes.borrow().get("style").unwrap().to_string();
+    assert!(!h1_style.ends_with(';'));
+    assert!(h1_style.contains("color:#369"));
+    assert!(h1_style.contains("margin:0 auto"));
+
+    let p = result_document.select_first("p").unwrap();
+    let p_style = p.attributes.borrow().get("style").unwrap().to_string();
+    assert!(p_style.contains("#123"));
+    assert!(p_style.contains("calc(1em + 0px)!important"));
+
+    let preserved_css = result_document
+        .select_first("head > style")
+        .unwrap()
+        .text_contents();
+    assert!(preserved_css.contains("{h1>a,p a{color:#00a}}"));
+}
+
+#[test]
+fn color_names() {
+    let html = r#"<style>p { color: #ff0000; }</style><p>Hello</p>"#;
+    let inlined_html = inline(html, None, options(true), None);
+    assert!(inlined_html.contains(r#"<p style="color:red">"#));
+}
+
+/// Named colors are serialized as written, so they are left out to compare the serializations.
+#[test]
+fn round_trip() {
+    let minified_html = inline(HTML, None, options(true), None);
+    let unminified_html = inline(HTML, None, options(false), None);
+    assert!(minified_html.len() < unminified_html.len());
+
+    assert_eq!(reinline(&minified_html), reinline(&unminified_html));
+}
+
+/// The `latin-ext` block of a Google Fonts stylesheet.
+#[test]
+fn round_trip_font_face() {
+    let html = r#"
+        <html>
+            <head>
+                <style>
+                    @font-face {
+                        font-family: "Roboto";
+                        font-style: normal;
+                        font-weight: 400;
+                        src: url(https://fonts.gstatic.com/s/roboto/v20/KFOmCnqEu92Fr1Mu7GxKOzY.woff2) format("woff2");
+                        unicode-range: U+0100-024F, U+0259, U+1E00-1EFF, U+2020, U+20A0-20AB, U+20AD-20CF, U+2113, U+2C60-2C7F, U+A720-A7FF;
+                    }
+
+                    p { font-family: "Roboto", sans-serif; }
+                </style>
+            </head>
+            <body>
+                <p>Hello</p>
+            </body>
+        </html>
+    "#;
+
+    let minified_html = inline(html, None, options(true), None);
+    let unminified_html = inline(html, None, options(false), None);
+
+    let unicode_range = unminified_html
+        .split("unicode-range: ")
+        .nth(1)
+        .and_then(|css| css.split(';').next())
+        .unwrap()
+        .replace(", ", ",");
+    assert!(minified_html.contains(&format!("unicode-range:{}", unicode_range)));
+
+    assert_eq!(reinline(&minified_html), reinline(&unminified_html));
+}
+
+#[test]
+fn keep_custom_property_values() {
+    let html = r#"<style>p { --gap: 0px; --color: #ff0000; margin: 0px; }</style><p>Hello</p>"#;
+    let result_document = parse_html().one(inline(html, None, options(true), None));
+
+    let p = result_document.select_first("p").unwrap();
+    let p_style = p.attributes.borrow().get("style").unwrap().to_string();
+    assert!(p_style.contains("--gap:0px"));
+    assert!(p_style.contains("--color:#ff0000"));
+    assert!(p_style.contains("margin:0"));
+}
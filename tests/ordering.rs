@@ -0,0 +1,83 @@
+use eyeliner::{inline, traits::*, AbstractOptions, AbstractSettings, Eyeliner};
+
+const HTML: &str = r#"
+    <html>
+        <head></head>
+        <body>
+            <p id="first">One</p>
+            <div id="second"><p id="third">Two</p></div>
+            <p id="fourth">Three</p>
+        </body>
+    </html>
+"#;
+
+const CSS: &str = r#"
+    p { z-index: 1; color: red; }
+    div { margin-top: 0; background-color: blue; }
+    p::after { content: "!"; }
+"#;
+
+#[test]
+fn styled_elements_in_document_order() {
+    let mut eyeliner = Eyeliner::new(
+        HTML,
+        Some(CSS.to_string()),
+        None::<AbstractOptions>,
+        None::<AbstractSettings>,
+    );
+    eyeliner.collect_rules().apply_rules();
+
+    let ids = eyeliner
+        .styled_elements
+        .iter()
+        .filter_map(|(node, _)| {
+            node.as_element()
+                .and_then(|element| element.attributes.borrow().get("id").map(String::from))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["first", "second", "third", "fourth"]);
+}
+
+#[test]
+fn byte_stable_output() {
+    let inlined_html = inline(HTML, Some(CSS.to_string()), None, None);
+    for _ in 0..10 {
+        assert_eq!(
+            inline(HTML, Some(CSS.to_string()), None, None),
+            inlined_html
+        );
+    }
+}
+
+#[test]
+fn sort_declarations() {
+    let options = AbstractOptions {
+        sort_declarations: Some(true),
+        ..Default::default()
+    };
+    let mut eyeliner = Eyeliner::new(
+        HTML,
+        Some(CSS.to_string()),
+        Some(options),
+        None::<AbstractSettings>,
+    );
+    eyeliner.collect_rules().apply_rules();
+
+    let styles = eyeliner
+        .styled_elements
+        .iter()
+        .filter_map(|(node, _)| {
+            node.as_element()
+                .and_then(|element| element.attributes.borrow().get("style").map(String::from))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        styles,
+        vec![
+            "color: red; z-index: 1;",
+            "background-color: blue; margin-top: 0px;",
+            "color: red; z-index: 1;",
+            "color: red; z-index: 1;",
+        ]
+    );
+}
@@ -0,0 +1,89 @@
+use eyeliner::{inline, inline_fragment, AbstractOptions};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const CSS: &str = r#"
+    p { color: red; }
+
+    @media (max-width: 600px) {
+        p { color: blue; }
+    }
+"#;
+
+#[test]
+fn style_placeholder() {
+    let html = r#"
+        <html>
+            <head>
+                <style data-eyeliner-preserve>.existing { color: green; }</style>
+                <title>Test</title>
+            </head>
+            <body><p>Hello</p></body>
+        </html>
+    "#;
+    let result_document = parse_html().one(inline(html, Some(CSS.to_string()), None, None));
+
+    let style_nodes = result_document.select("style").unwrap().collect::<Vec<_>>();
+    assert_eq!(style_nodes.len(), 1);
+
+    let style_node = &style_nodes[0];
+    assert!(!style_node
+        .attributes
+        .borrow()
+        .contains("data-eyeliner-preserve"));
+
+    let preserved_css = style_node.text_contents();
+    assert!(preserved_css.starts_with(".existing { color: green; }"));
+    assert!(preserved_css.contains("@media (max-width: 600px)"));
+
+    // The placeholder's own CSS isn't inlined.
+    let p = result_document.select_first("p").unwrap();
+    assert_eq!(p.attributes.borrow().get("style"), Some("color: red;"));
+}
+
+#[test]
+fn comment_placeholder() {
+    let html = r#"
+        <html>
+            <head>
+                <title>Test</title>
+                <!-- eyeliner:preserved -->
+                <meta name="viewport" content="width=device-width">
+            </head>
+            <body><p>Hello</p></body>
+        </html>
+    "#;
+    let inlined_html = inline(html, Some(CSS.to_string()), None, None);
+
+    assert!(!inlined_html.contains("eyeliner:preserved"));
+    let style_index = inlined_html.find("<style>").unwrap();
+    assert!(style_index < inlined_html.find("<meta").unwrap());
+    assert!(style_index > inlined_html.find("<title>").unwrap());
+}
+
+#[test]
+fn comment_placeholder_in_fragment() {
+    let html = r#"<!-- eyeliner:preserved --><p>Hello</p>"#;
+    let inlined_html = inline_fragment(html, Some(CSS.to_string()), None, None);
+
+    assert!(inlined_html.starts_with("<style>@media (max-width: 600px)"));
+    assert!(inlined_html.ends_with(r#"<p style="color: red;">Hello</p>"#));
+}
+
+#[test]
+fn prepend_preserved_css() {
+    let html = r#"<html><head><title>Test</title></head><body><p>Hello</p></body></html>"#;
+    let options = AbstractOptions {
+        prepend_preserved_css: Some(true),
+        ..Default::default()
+    };
+    let result_document =
+        parse_html().one(inline(html, Some(CSS.to_string()), Some(options), None));
+
+    let head = result_document.select_first("head").unwrap();
+    let first_child = head.as_node().first_child().unwrap();
+    assert_eq!(
+        first_child.as_element().map(|e| e.name.local.to_string()),
+        Some(String::from("style"))
+    );
+}
@@ -0,0 +1,30 @@
+use eyeliner::inline;
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+#[test]
+fn preserved_order() {
+    let result_document = parse_html().one(inline(
+        include_str!("./preserved_order.html"),
+        None,
+        None,
+        None,
+    ));
+
+    let title = result_document.select_first(".title").unwrap();
+    assert_eq!(
+        title.attributes.borrow().get("style"),
+        Some("font-size: 24px;")
+    );
+
+    let preserved_css = result_document
+        .select_first("head > style")
+        .unwrap()
+        .text_contents();
+    let position = |css: &str| preserved_css.find(css).unwrap();
+
+    // The narrower media query comes last, so it still wins on screens where both apply.
+    assert!(position("@font-face") < position("max-width: 600px"));
+    assert!(position("max-width: 600px") < position(".title:hover"));
+    assert!(position(".title:hover") < position("max-width: 400px"));
+}
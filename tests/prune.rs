@@ -0,0 +1,60 @@
+use eyeliner::{inline, traits::*, AbstractOptions, AbstractSettings, Eyeliner, Severity};
+
+const HTML: &str = r#"
+    <html>
+        <head>
+            <style>
+                @media (max-width: 600px) {
+                    .title { font-size: 20px; }
+                    .missing { color: red; }
+                    a:hover { color: blue; }
+                }
+
+                @media print {
+                    .missing { display: none; }
+                }
+            </style>
+        </head>
+        <body>
+            <h1 class="title">Hello, <a href="https://example.com">world</a>!</h1>
+        </body>
+    </html>
+"#;
+
+fn options(prune_media_queries: bool) -> Option<AbstractOptions> {
+    Some(AbstractOptions {
+        prune_media_queries: Some(prune_media_queries),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn prune_media_queries() {
+    let mut eyeliner = Eyeliner::new(HTML, None, options(true), None::<AbstractSettings>);
+    eyeliner.collect_rules().apply_rules().prune_media_queries();
+
+    let preserved_css = eyeliner
+        .rules
+        .preserved
+        .iter()
+        .map(|rule| rule.css.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert!(preserved_css.contains("@media (max-width: 600px)"));
+    assert!(preserved_css.contains(".title"));
+    assert!(preserved_css.contains("a:hover"));
+    assert!(!preserved_css.contains(".missing"));
+    assert!(!preserved_css.contains("@media print"));
+
+    assert!(eyeliner
+        .diagnostics
+        .iter()
+        .any(|d| d.severity == Severity::Info && d.message.starts_with("Pruned ")));
+}
+
+#[test]
+fn keep_media_queries() {
+    let inlined_html = inline(HTML, None, options(false), None);
+    assert!(inlined_html.contains(".missing"));
+    assert!(inlined_html.contains("@media print"));
+}
@@ -0,0 +1,62 @@
+use eyeliner::{inline, AbstractOptions, AbstractSettings};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const HTML: &str = r#"
+    <html>
+        <head>
+            <style>
+                a { color: red; }
+                a:hover, a:focus { color: blue; }
+                .btn:active { top: 1px; }
+            </style>
+        </head>
+        <body>
+            <a class="btn" href="/">Link</a>
+        </body>
+    </html>
+"#;
+
+#[test]
+fn preserve_pseudo_classes() {
+    let result_document = parse_html().one(inline(HTML, None, None, None));
+
+    let link = result_document.select_first("a").unwrap();
+    assert_eq!(link.attributes.borrow().get("style"), Some("color: red;"));
+
+    let preserved_css = result_document
+        .select_first("head > style")
+        .unwrap()
+        .text_contents();
+    assert!(preserved_css.contains("a:hover, a:focus { color: blue; }"));
+    assert!(preserved_css.contains(".btn:active { top: 1px; }"));
+}
+
+#[test]
+fn drop_pseudo_classes() {
+    let options = AbstractOptions {
+        preserve_pseudo_classes: Some(false),
+        ..Default::default()
+    };
+    let result_document = parse_html().one(inline(HTML, None, Some(options), None));
+
+    let link = result_document.select_first("a").unwrap();
+    assert_eq!(link.attributes.borrow().get("style"), Some("color: red;"));
+    assert!(result_document.select_first("style").is_err());
+}
+
+#[test]
+fn exclude_properties_from_pseudo_classes() {
+    let settings = AbstractSettings {
+        excluded_properties: Some(vec![String::from("top")]),
+        ..Default::default()
+    };
+    let result_document = parse_html().one(inline(HTML, None, None, Some(settings)));
+
+    let preserved_css = result_document
+        .select_first("head > style")
+        .unwrap()
+        .text_contents();
+    assert!(preserved_css.contains("a:hover, a:focus { color: blue; }"));
+    assert!(!preserved_css.contains(".btn:active"));
+}
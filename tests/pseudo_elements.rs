@@ -0,0 +1,78 @@
+use eyeliner::{inline, AbstractOptions};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+fn options() -> Option<AbstractOptions> {
+    Some(AbstractOptions {
+        apply_pseudo_elements: Some(true),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn before_and_after() {
+    let html = r#"<a class="link" href="https://example.com">Example</a>"#;
+    let css = r#"
+        .link::before {
+            content: "\2192  ";
+            color: red;
+        }
+
+        .link:after {
+            content: " (" attr(href) ")";
+        }
+    "#;
+
+    let result_document = parse_html().one(inline(html, Some(css.to_string()), options(), None));
+    let link = result_document.select_first(".link").unwrap();
+    let children = link.as_node().children().collect::<Vec<_>>();
+    assert_eq!(children.len(), 3);
+
+    let before = children[0].as_element().unwrap();
+    assert_eq!(&*before.name.local, "span");
+    assert_eq!(before.attributes.borrow().get("style"), Some("color: red;"));
+    assert_eq!(children[0].text_contents(), "\u{2192} ");
+
+    assert_eq!(children[1].text_contents(), "Example");
+
+    let after = children[2].as_element().unwrap();
+    assert_eq!(&*after.name.local, "span");
+    assert_eq!(after.attributes.borrow().get("style"), None);
+    assert_eq!(children[2].text_contents(), " (https://example.com)");
+}
+
+#[test]
+fn content_none() {
+    let html = r#"<p class="note">One</p><p class="note plain">Two</p>"#;
+    let css = r#"
+        .note::before {
+            content: "Note: ";
+            font-weight: bold;
+        }
+
+        .note.plain::before {
+            content: none;
+        }
+    "#;
+
+    let result_document = parse_html().one(inline(html, Some(css.to_string()), options(), None));
+    let spans = result_document
+        .select("p > span")
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(
+        spans[0].attributes.borrow().get("style"),
+        Some("font-weight: bold;")
+    );
+    assert_eq!(spans[0].text_contents(), "Note: ");
+}
+
+#[test]
+fn disabled() {
+    let html = r#"<p class="note">One</p>"#;
+    let css = r#".note::before { content: "Note: "; }"#;
+
+    let result_document = parse_html().one(inline(html, Some(css.to_string()), None, None));
+    assert!(result_document.select_first("span").is_err());
+}
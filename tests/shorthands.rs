@@ -0,0 +1,78 @@
+use eyeliner::{inline, AbstractOptions};
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const HTML: &str = r#"
+    <html>
+        <head></head>
+        <body>
+            <table><tr><td>Cell</td></tr></table>
+            <p class="a">Hello</p>
+        </body>
+    </html>
+"#;
+
+const CSS: &str = r#"
+    td { background: red url(x.png) no-repeat; }
+    p { padding: 1px; }
+    p.a { padding-left: 2px; }
+"#;
+
+#[test]
+fn collapse_shorthands() {
+    let result_document = parse_html().one(inline(HTML, Some(CSS.to_string()), None, None));
+
+    let p = result_document.select_first("p").unwrap();
+    assert_eq!(
+        p.attributes.borrow().get("style"),
+        Some("padding: 1px 1px 1px 2px;")
+    );
+}
+
+#[test]
+fn expand_shorthands() {
+    let options = AbstractOptions {
+        expand_shorthands: Some(true),
+        ..Default::default()
+    };
+    let result_document =
+        parse_html().one(inline(HTML, Some(CSS.to_string()), Some(options), None));
+
+    let p = result_document.select_first("p").unwrap();
+    assert_eq!(
+        p.attributes.borrow().get("style"),
+        Some("padding-top: 1px; padding-right: 1px; padding-bottom: 1px; padding-left: 2px;")
+    );
+}
+
+#[test]
+fn map_longhands_set_by_shorthands() {
+    let result_document = parse_html().one(inline(HTML, Some(CSS.to_string()), None, None));
+
+    let td = result_document.select_first("td").unwrap();
+    let attributes = td.attributes.borrow();
+    assert_eq!(attributes.get("bgcolor"), Some("red"));
+    assert_eq!(attributes.get("background"), Some("x.png"));
+}
+
+#[test]
+fn skip_initial_background_image() {
+    let css = "td { background: red; }";
+    let result_document = parse_html().one(inline(HTML, Some(css.to_string()), None, None));
+
+    let td = result_document.select_first("td").unwrap();
+    let attributes = td.attributes.borrow();
+    assert_eq!(attributes.get("bgcolor"), Some("red"));
+    assert_eq!(attributes.get("background"), None);
+}
+
+#[test]
+fn skip_initial_background_color() {
+    let css = "td { background: url(x.png); }";
+    let result_document = parse_html().one(inline(HTML, Some(css.to_string()), None, None));
+
+    let td = result_document.select_first("td").unwrap();
+    let attributes = td.attributes.borrow();
+    assert_eq!(attributes.get("bgcolor"), None);
+    assert_eq!(attributes.get("background"), Some("x.png"));
+}
@@ -0,0 +1,97 @@
+use eyeliner::{
+    inline, inline_result, traits::*, try_inline, try_inline_result, AbstractOptions,
+    AbstractSettings, Error, Eyeliner, Severity,
+};
+
+const HTML: &str = r#"
+    <html>
+        <head>
+            <style>
+                p { color: red; }
+
+                @media (max-width: 600px) {
+                    p { color: blue; }
+                }
+            </style>
+        </head>
+        <body>
+            <p>Hello, world!</p>
+        </body>
+    </html>
+"#;
+
+fn settings(size_budget: usize) -> Option<AbstractSettings> {
+    Some(AbstractSettings {
+        size_budget: Some(size_budget),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn size_report() {
+    let mut eyeliner = Eyeliner::new(HTML, None, None::<AbstractOptions>, settings(100));
+    eyeliner
+        .collect_rules()
+        .apply_rules()
+        .insert_preserved_css();
+    let html = eyeliner.to_string();
+    eyeliner.check_size_budget(&html, "");
+
+    let size_report = eyeliner.size_report.unwrap();
+    assert_eq!(size_report.total, html.len());
+    assert_eq!(size_report.inline_styles, "color: red;".len());
+    assert!(size_report.preserved_css > 0);
+    assert_eq!(
+        size_report.inline_styles + size_report.preserved_css + size_report.markup,
+        size_report.total
+    );
+
+    assert!(eyeliner
+        .diagnostics
+        .iter()
+        .any(|d| d.severity == Severity::Warning && d.message.contains("size budget")));
+}
+
+#[test]
+fn enforce_size_budget() {
+    let options = Some(AbstractOptions {
+        enforce_size_budget: Some(true),
+        ..Default::default()
+    });
+
+    match try_inline(HTML, None, options.clone(), settings(100)) {
+        Err(Error::SizeBudgetExceeded { size, budget }) => {
+            assert!(size > budget);
+            assert_eq!(budget, 100);
+        }
+        result => panic!("expected a size budget error, got {:?}", result),
+    }
+
+    assert!(try_inline(HTML, None, options.clone(), None).is_ok());
+    assert!(try_inline(HTML, None, None, settings(100)).is_ok());
+    assert_eq!(
+        inline(HTML, None, options, settings(100)),
+        inline(HTML, None, None, None)
+    );
+}
+
+#[test]
+fn enforce_size_budget_with_preserved_css() {
+    let options = Some(AbstractOptions {
+        enforce_size_budget: Some(true),
+        ..Default::default()
+    });
+
+    // The HTML fits the budget on its own, but not with the preserved CSS the caller inserts.
+    let result = inline_result(HTML, None, None, None);
+    assert!(!result.preserved_css.is_empty());
+    let size_budget = result.html.len();
+
+    match try_inline_result(HTML, None, options, settings(size_budget)) {
+        Err(Error::SizeBudgetExceeded { size, budget }) => {
+            assert_eq!(size, result.html.len() + result.preserved_css.len());
+            assert_eq!(budget, size_budget);
+        }
+        result => panic!("expected a size budget error, got {:?}", result),
+    }
+}
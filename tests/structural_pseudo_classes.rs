@@ -0,0 +1,111 @@
+use eyeliner::inline;
+use kuchiki::parse_html;
+use kuchiki::traits::*;
+
+const HTML: &str = r#"
+    <html id="root">
+        <head></head>
+        <body id="body">
+            <ul id="list">
+                <li id="li1">One</li>
+                <li id="li2">Two</li>
+                <li id="li3" class="x">Three</li>
+                <li id="li4"></li>
+            </ul>
+            <div id="div">
+                <p id="p1">One</p>
+                <span id="span">Two</span>
+                <p id="p2">Three</p>
+            </div>
+            <section id="section"><em id="em">Only</em></section>
+        </body>
+    </html>
+"#;
+
+/// Returns the IDs of the elements that a style rule with `selector` is inlined into.
+fn matched_ids(selector: &str) -> Vec<String> {
+    let css = format!("{} {{ color: red; }}", selector);
+    let result_document = parse_html().one(inline(HTML, Some(css), None, None));
+
+    result_document
+        .select("[style]")
+        .unwrap()
+        .filter_map(|element| element.attributes.borrow().get("id").map(String::from))
+        .collect()
+}
+
+#[test]
+fn conformance_matrix() {
+    let matrix: &[(&str, &[&str])] = &[
+        ("li:nth-child(even)", &["li2", "li4"]),
+        ("li:nth-child(2n+1)", &["li1", "li3"]),
+        ("li:nth-last-child(1)", &["li4"]),
+        ("p:nth-of-type(2)", &["p2"]),
+        ("p:nth-last-of-type(2)", &["p1"]),
+        ("li:first-child", &["li1"]),
+        ("li:last-child", &["li4"]),
+        ("p:first-of-type", &["p1"]),
+        ("p:last-of-type", &["p2"]),
+        ("em:only-child", &["em"]),
+        ("div > :only-of-type", &["span"]),
+        ("li:empty", &["li4"]),
+        (":root", &["root"]),
+        ("li:not(.x)", &["li1", "li2", "li4"]),
+        ("li:not(.x, #li1)", &["li2", "li4"]),
+        ("li:not(:first-child):not(:last-child)", &["li2", "li3"]),
+        (":is(p, em)", &["p1", "p2", "em"]),
+        (":is(div, section) > :first-child", &["p1", "em"]),
+        ("li:is(.x, :empty)", &["li3", "li4"]),
+        (":where(ul) > :nth-child(2)", &["li2"]),
+        ("div :where(p:last-of-type, span)", &["span", "p2"]),
+        ("p:is(span)", &[]),
+    ];
+
+    for (selector, expected_ids) in matrix {
+        let mut ids = matched_ids(selector);
+        let mut expected_ids = expected_ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        expected_ids.sort();
+        assert_eq!(ids, expected_ids, "selector `{}`", selector);
+    }
+}
+
+#[test]
+fn where_has_no_specificity() {
+    let css = r#"
+        :where(#list) li { color: blue; }
+        li { color: red; }
+    "#;
+    let result_document = parse_html().one(inline(HTML, Some(css.to_string()), None, None));
+
+    let li = result_document.select_first("#li1").unwrap();
+    assert_eq!(li.attributes.borrow().get("style"), Some("color: red;"));
+}
+
+#[test]
+fn is_has_the_specificity_of_its_most_specific_selector() {
+    // `:is(#missing, ul) li` is as specific as `#missing li`, even for elements matched through `ul`.
+    let css = r#"
+        :is(#missing, ul) li { color: blue; }
+        ul li.x { color: red; }
+    "#;
+    let result_document = parse_html().one(inline(HTML, Some(css.to_string()), None, None));
+
+    let li = result_document.select_first("#li3").unwrap();
+    assert_eq!(li.attributes.borrow().get("style"), Some("color: blue;"));
+}
+
+#[test]
+fn not_has_the_specificity_of_its_most_specific_selector() {
+    let css = r#"
+        li:not(.y, #missing) { color: blue; }
+        ul li.x { color: red; }
+    "#;
+    let result_document = parse_html().one(inline(HTML, Some(css.to_string()), None, None));
+
+    let li = result_document.select_first("#li3").unwrap();
+    assert_eq!(li.attributes.borrow().get("style"), Some("color: blue;"));
+}
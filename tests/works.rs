@@ -1,4 +1,4 @@
-use eyeliner::inline;
+use eyeliner::{inline, AbstractOptions};
 use kuchiki::parse_html;
 use kuchiki::traits::*;
 
@@ -42,3 +42,69 @@ fn test() {
         assert_eq!(result_style, expected_style);
     }
 }
+
+#[test]
+fn cascade() {
+    let html = r#"
+        <div id="hero">
+            <p class="lead">Hello, world!</p>
+        </div>
+    "#;
+    let css = r#"
+        #hero p {
+            color: red;
+        }
+
+        p {
+            color: blue;
+        }
+
+        .lead {
+            font-weight: bold !important;
+        }
+
+        p.lead {
+            font-weight: normal;
+        }
+    "#;
+
+    let result_document = parse_html().one(inline(html, Some(css.to_string()), None, None));
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    let result_style = result_attributes.get("style").unwrap();
+
+    assert!(result_style.contains("color: red"));
+    assert!(result_style.contains("font-weight: bold"));
+}
+
+#[test]
+fn inline_style_precedence() {
+    let html = r#"<p class="red" style="color: blue; font-weight: normal;">Hello, world!</p>"#;
+    let css = r#"
+        .red {
+            color: red;
+            font-weight: bold !important;
+        }
+    "#;
+
+    let result_document = parse_html().one(inline(html, Some(css.to_string()), None, None));
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    let result_style = result_attributes.get("style").unwrap();
+
+    assert!(result_style.contains("color: blue"));
+    assert!(result_style.contains("font-weight: bold"));
+
+    let options = AbstractOptions {
+        inline_style_precedence: Some(false),
+        ..AbstractOptions::default()
+    };
+    let result_document =
+        parse_html().one(inline(html, Some(css.to_string()), Some(options), None));
+    let result_node = result_document.select_first("p").unwrap();
+    let result_attributes = result_node.attributes.borrow();
+    let result_style = result_attributes.get("style").unwrap();
+
+    assert!(result_style.contains("color: red"));
+    assert!(result_style.contains("font-weight: bold"));
+}